@@ -0,0 +1,64 @@
+use crate::browser::{Tab, TabId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Which tab takes over when the active tab is closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivationPolicy {
+    /// The tab that was active most recently before it.
+    #[default]
+    Recent,
+    /// The tab that opened it, falling back to the most recent one.
+    Opener,
+    /// The tab to its right, or to its left when it was the last one.
+    Right,
+}
+
+impl ActivationPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivationPolicy::Recent => "recent",
+            ActivationPolicy::Opener => "opener",
+            ActivationPolicy::Right => "right",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "opener" => ActivationPolicy::Opener,
+            "right" => ActivationPolicy::Right,
+            _ => ActivationPolicy::Recent,
+        }
+    }
+}
+
+/// Picks the tab to activate after `closed` was closed.
+///
+/// `tabs` no longer holds the closed tab and has dense positions; `recent` is most
+/// recently used first.
+pub fn next_active_tab(
+    closed: &Tab,
+    tabs: &HashMap<TabId, Tab>,
+    recent: &[TabId],
+    policy: ActivationPolicy,
+) -> Option<TabId> {
+    let most_recent = || recent.iter().find(|id| tabs.contains_key(*id)).cloned();
+    let neighbour = || {
+        let right = closed.position.min(tabs.len() as i32 - 1);
+        tabs.values()
+            .find(|t| t.position == right)
+            .map(|t| t.id.clone())
+    };
+
+    match policy {
+        ActivationPolicy::Recent => most_recent().or_else(neighbour),
+        ActivationPolicy::Opener => closed
+            .opener_id
+            .clone()
+            .filter(|id| tabs.contains_key(id))
+            .or_else(most_recent)
+            .or_else(neighbour),
+        ActivationPolicy::Right => neighbour(),
+    }
+}
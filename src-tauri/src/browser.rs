@@ -1,10 +1,44 @@
+use crate::activation::{next_active_tab, ActivationPolicy};
+use crate::cdp::{recv_event, wait_for_browser_ws_url, CdpConnection, CdpEvent, CdpSession};
+use crate::closed::{navigation_entries, ClosedTab, NavigationEntry, ReopenedTab, MAX_CLOSED_TABS};
 use crate::error::{BrowserError, Result};
+use crate::events::{emit, watch_downloads, BrowserEvent};
+use crate::find::{FindResult, FindSession, FrameMatches, FIND_SCRIPT};
+use crate::groups::{
+    group_after_move, index_outside_groups, remove_empty_groups, GroupColor, GroupId, TabGroup,
+    TabGroupUpdate,
+};
+use crate::intercept::{fetch_enable_params, pump_fetch};
+use crate::marks::{
+    element_label, overlay_expression, quad_bounds, Mark, MarkedScreenshot, MARKABLE_SELECTOR,
+    MAX_MARKS, REMOVE_OVERLAY,
+};
+use crate::metrics::{renderer_pid_from_trace, ProcessInfo, TabMetrics};
+use crate::popups::{popup_host, wait_for_target_url, watch_popups, PopupPolicy, PopupRule};
+use crate::proxy::{ProxyConfig, ProxyCredentials, ProxyMode};
+use crate::restore::SessionTab;
+use crate::rules::RequestRule;
+use crate::screencast::{pump_frames, KeyInput, MouseEventKind, MouseInput, ScreencastSettings};
+use crate::script::{EvaluationResult, ScriptWorld};
+use crate::security::{pump_security, CertificateError, TabSecurity};
+use crate::sitedata::{cookie_origins, site_origin, storage_types_param, SiteData, SiteDataType};
+use crate::storage::Settings;
+use crate::throttling::NetworkEmulation;
+use crate::windows::{new_window_id, BrowserWindow, WindowId, MAIN_WINDOW};
+use crate::zoom::{
+    next_zoom_level, zoom_for_url, zoom_origin, ZoomAction, ZoomLevel, DEFAULT_ZOOM,
+};
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 pub type TabId = String;
@@ -15,8 +49,53 @@ pub struct Tab {
     pub url: String,
     pub title: String,
     pub favicon: Option<String>,
+    /// The window whose tab strip shows this tab.
+    pub window_id: WindowId,
+    /// Position within its window's tab strip.
     pub position: i32,
+    /// Whether this is the tab shown in its window.
     pub is_active: bool,
+    pub lifecycle_state: LifecycleState,
+    /// The tab whose page opened this one via `window.open` or `target=_blank`.
+    pub opener_id: Option<TabId>,
+    /// Set for tabs in a private browser context; `None` means the default profile.
+    pub browser_context_id: Option<String>,
+    pub group_id: Option<GroupId>,
+    /// Pinned tabs sit before all others, are never discarded and always come back at startup.
+    pub pinned: bool,
+    pub security: TabSecurity,
+}
+
+/// Where a tab sits in the memory-saving lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleState {
+    Active,
+    Frozen,
+    Discarded,
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleSettings {
+    pub enabled: bool,
+    pub freeze_after: Duration,
+    pub discard_after: Duration,
+}
+
+impl Default for LifecycleSettings {
+    fn default() -> Self {
+        Self::from(&Settings::default())
+    }
+}
+
+impl From<&Settings> for LifecycleSettings {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            enabled: settings.tab_lifecycle_enabled,
+            freeze_after: Duration::from_secs(settings.tab_freeze_after_minutes * 60),
+            discard_after: Duration::from_secs(settings.tab_discard_after_minutes * 60),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,8 +106,48 @@ pub struct NavigationRequest {
 pub struct BrowserManager {
     chrome_process: Option<Child>,
     tabs: Arc<RwLock<HashMap<TabId, Tab>>>,
+    /// The active tab of the focused window.
     active_tab: Arc<RwLock<Option<TabId>>>,
+    /// Open windows in the order they were opened, the main window first.
+    windows: Arc<RwLock<Vec<WindowId>>>,
     cdp_port: u16,
+    cdp: Arc<RwLock<Option<Arc<CdpConnection>>>>,
+    sessions: Arc<RwLock<HashMap<TabId, CdpSession>>>,
+    file_chooser_files: Arc<RwLock<HashMap<TabId, Vec<String>>>>,
+    file_chooser_pumps: Arc<RwLock<HashMap<TabId, JoinHandle<()>>>>,
+    lifecycle_settings: Arc<RwLock<LifecycleSettings>>,
+    inactive_since: Arc<RwLock<HashMap<TabId, Instant>>>,
+    discarded_scroll: Arc<RwLock<HashMap<TabId, (f64, f64)>>>,
+    find_sessions: Arc<RwLock<HashMap<TabId, FindSession>>>,
+    screencasts: Arc<RwLock<HashMap<TabId, JoinHandle<()>>>>,
+    popup_policies: Arc<RwLock<HashMap<String, PopupPolicy>>>,
+    proxy: Arc<RwLock<ProxyConfig>>,
+    proxy_credentials: Arc<RwLock<Option<ProxyCredentials>>>,
+    /// Private browser contexts, with the credentials for the proxy each was opened with.
+    browser_contexts: Arc<RwLock<HashMap<String, Option<ProxyCredentials>>>>,
+    fetch_pumps: Arc<RwLock<HashMap<TabId, JoinHandle<()>>>>,
+    request_rules: Arc<RwLock<Vec<RequestRule>>>,
+    network_emulation: Arc<RwLock<HashMap<TabId, NetworkEmulation>>>,
+    zoom_levels: Arc<RwLock<HashMap<String, f64>>>,
+    security_pumps: Arc<RwLock<HashMap<TabId, JoinHandle<()>>>>,
+    navigation_pumps: Arc<RwLock<HashMap<TabId, JoinHandle<()>>>>,
+    isolated_worlds: IsolatedWorlds,
+    /// Hosts the user proceeded to past a certificate error, for this run only.
+    allowed_cert_hosts: Arc<RwLock<HashSet<String>>>,
+    /// The marks from each tab's latest set-of-marks screenshot.
+    marks: Arc<RwLock<HashMap<TabId, Vec<Mark>>>>,
+    groups: Arc<RwLock<HashMap<GroupId, TabGroup>>>,
+    /// Most recently closed first, at most `MAX_CLOSED_TABS`.
+    closed_tabs: Arc<RwLock<VecDeque<ClosedTab>>>,
+    /// Tab ids by when they were last activated, most recent first.
+    recent_tabs: Arc<RwLock<Vec<TabId>>>,
+    activation_policy: Arc<RwLock<ActivationPolicy>>,
+}
+
+impl Default for BrowserManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BrowserManager {
@@ -37,20 +156,51 @@ impl BrowserManager {
             chrome_process: None,
             tabs: Arc::new(RwLock::new(HashMap::new())),
             active_tab: Arc::new(RwLock::new(None)),
+            windows: Arc::new(RwLock::new(vec![MAIN_WINDOW.to_string()])),
             cdp_port: 9222,
+            cdp: Arc::new(RwLock::new(None)),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            file_chooser_files: Arc::new(RwLock::new(HashMap::new())),
+            file_chooser_pumps: Arc::new(RwLock::new(HashMap::new())),
+            lifecycle_settings: Arc::new(RwLock::new(LifecycleSettings::default())),
+            inactive_since: Arc::new(RwLock::new(HashMap::new())),
+            discarded_scroll: Arc::new(RwLock::new(HashMap::new())),
+            find_sessions: Arc::new(RwLock::new(HashMap::new())),
+            screencasts: Arc::new(RwLock::new(HashMap::new())),
+            popup_policies: Arc::new(RwLock::new(HashMap::new())),
+            proxy: Arc::new(RwLock::new(ProxyConfig::default())),
+            proxy_credentials: Arc::new(RwLock::new(None)),
+            browser_contexts: Arc::new(RwLock::new(HashMap::new())),
+            fetch_pumps: Arc::new(RwLock::new(HashMap::new())),
+            request_rules: Arc::new(RwLock::new(Vec::new())),
+            network_emulation: Arc::new(RwLock::new(HashMap::new())),
+            zoom_levels: Arc::new(RwLock::new(HashMap::new())),
+            security_pumps: Arc::new(RwLock::new(HashMap::new())),
+            navigation_pumps: Arc::new(RwLock::new(HashMap::new())),
+            isolated_worlds: Arc::new(RwLock::new(HashMap::new())),
+            allowed_cert_hosts: Arc::new(RwLock::new(HashSet::new())),
+            marks: Arc::new(RwLock::new(HashMap::new())),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+            closed_tabs: Arc::new(RwLock::new(VecDeque::new())),
+            recent_tabs: Arc::new(RwLock::new(Vec::new())),
+            activation_policy: Arc::new(RwLock::new(ActivationPolicy::default())),
         }
     }
 
-    pub async fn launch_chrome(&mut self, app_handle: &tauri::AppHandle) -> Result<()> {
+    /// Starts Chrome with remote debugging and returns its DevTools port. Connecting is
+    /// left to `start_chrome`, so the manager isn't locked while Chrome boots.
+    pub async fn launch_chrome(&mut self, app_handle: &tauri::AppHandle) -> Result<u16> {
         if self.chrome_process.is_some() {
             info!("Chrome already running");
-            return Ok(());
+            return Ok(self.cdp_port);
         }
 
         let user_data_dir = app_handle
             .path()
             .app_data_dir()
-            .map_err(|e| BrowserError::Configuration(format!("Cannot find app data directory: {}", e)))?
+            .map_err(|e| {
+                BrowserError::Configuration(format!("Cannot find app data directory: {}", e))
+            })?
             .join("chrome-profile");
 
         std::fs::create_dir_all(&user_data_dir).map_err(|e| {
@@ -59,6 +209,7 @@ impl BrowserManager {
 
         let chrome_cmd = self.find_chrome()?;
         let port = self.cdp_port;
+        let proxy_args = self.proxy.read().await.chrome_args();
 
         info!("Launching Chrome from {:?} on port {}", chrome_cmd, port);
 
@@ -68,21 +219,280 @@ impl BrowserManager {
             .arg("--no-default-browser-check")
             .arg("--disable-default-apps")
             .arg("--disable-popup-blocking")
-            .arg(format!("--user-data-dir={}", user_data_dir.to_string_lossy()))
+            .arg(format!(
+                "--user-data-dir={}",
+                user_data_dir.to_string_lossy()
+            ))
+            .args(proxy_args)
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
             .map_err(|e| BrowserError::Cdp(format!("Failed to launch Chrome: {}", e)))?;
 
         self.chrome_process = Some(child);
+        info!("Chrome launched");
+        Ok(port)
+    }
+
+    /// Connects to a browser-level DevTools endpoint and starts the event watchers.
+    pub(crate) async fn connect_to(&self, ws_url: &str) -> Result<()> {
+        let conn = CdpConnection::connect(ws_url).await?;
+
+        tokio::spawn(watch_popups(conn.subscribe()));
+        tokio::spawn(watch_target_info(conn.subscribe()));
+        tokio::spawn(watch_downloads(conn.subscribe()));
+        conn.send(
+            None,
+            "Target.setDiscoverTargets",
+            json!({ "discover": true }),
+        )
+        .await?;
+        conn.send(
+            None,
+            "Browser.setDownloadBehavior",
+            json!({ "behavior": "default", "eventsEnabled": true }),
+        )
+        .await?;
+
+        *self.cdp.write().await = Some(conn);
+        debug!("Connected to browser DevTools at {}", ws_url);
+        Ok(())
+    }
+
+    /// Returns the CDP session for a tab, opening a Chrome target for it on first use.
+    ///
+    /// A discarded tab is woken, so it reloads where it was and counts as active again.
+    pub async fn session(&self, tab_id: &str) -> Result<CdpSession> {
+        if let Some(session) = self.sessions.read().await.get(tab_id) {
+            return Ok(session.clone());
+        }
+
+        let (url, state) = self
+            .tabs
+            .read()
+            .await
+            .get(tab_id)
+            .map(|t| (t.url.clone(), t.lifecycle_state))
+            .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
+
+        if state == LifecycleState::Discarded {
+            self.wake_tab(tab_id, state).await?;
+            return self
+                .sessions
+                .read()
+                .await
+                .get(tab_id)
+                .cloned()
+                .ok_or_else(|| BrowserError::NotFound(format!("Tab {} has no target", tab_id)));
+        }
+        self.open_session(tab_id, &url).await
+    }
+
+    async fn connection(&self) -> Result<Arc<CdpConnection>> {
+        self.cdp
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| BrowserError::Cdp("Chrome is not connected".to_string()))
+    }
+
+    async fn open_session(&self, tab_id: &str, url: &str) -> Result<CdpSession> {
+        let conn = self.connection().await?;
+        let browser_context_id = self
+            .tabs
+            .read()
+            .await
+            .get(tab_id)
+            .and_then(|t| t.browser_context_id.clone());
+
+        let mut params = json!({ "url": url });
+        if let Some(context_id) = &browser_context_id {
+            params["browserContextId"] = json!(context_id);
+        }
+
+        let target = conn.send(None, "Target.createTarget", params).await?;
+        let target_id = target
+            .get("targetId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| BrowserError::Cdp("createTarget returned no targetId".to_string()))?;
+
+        let session = CdpSession::attach(conn, target_id).await?;
+        self.sessions
+            .write()
+            .await
+            .insert(tab_id.to_string(), session.clone());
+        self.update_interception(tab_id, &session, browser_context_id.as_deref())
+            .await?;
+        self.start_security_monitoring(tab_id, &session).await?;
+        self.start_navigation_watch(tab_id, &session).await?;
+
+        // Reopened tabs keep the network conditions they were emulating
+        let emulation = self.network_emulation.read().await.get(tab_id).copied();
+        if let Some(emulation) = emulation {
+            session.send("Network.enable", json!({})).await?;
+            session
+                .send(
+                    "Network.emulateNetworkConditions",
+                    emulation.to_cdp_params(),
+                )
+                .await?;
+            session
+                .send(
+                    "Emulation.setCPUThrottlingRate",
+                    emulation.to_cpu_throttling_params(),
+                )
+                .await?;
+        }
+
+        let zoom = self.zoom_for(url).await;
+        if zoom != DEFAULT_ZOOM {
+            apply_zoom(&self.isolated_worlds, tab_id, &session, zoom).await?;
+        }
+
+        debug!("Attached tab {} to target {}", tab_id, target_id);
+        Ok(session)
+    }
+
+    /// Intercepts a session's requests while there are enabled request rules or its proxy
+    /// needs credentials, and stops once there's neither so requests no longer pause.
+    async fn update_interception(
+        &self,
+        tab_id: &str,
+        session: &CdpSession,
+        browser_context_id: Option<&str>,
+    ) -> Result<()> {
+        let credentials = match browser_context_id {
+            Some(context_id) => self
+                .browser_contexts
+                .read()
+                .await
+                .get(context_id)
+                .cloned()
+                .flatten(),
+            None if self.proxy.read().await.mode == ProxyMode::Direct => None,
+            None => self.proxy_credentials.read().await.clone(),
+        };
+        let rewriting = self.request_rules.read().await.iter().any(|r| r.enabled);
+        if credentials.is_none() && !rewriting {
+            if let Some(pump) = self.fetch_pumps.write().await.remove(tab_id) {
+                pump.abort();
+                session.send("Fetch.disable", json!({})).await?;
+            }
+            return Ok(());
+        }
+        // Enabling again updates the patterns as the rules change
+        let params = fetch_enable_params(&self.request_rules.read().await, credentials.is_some());
+        if self.fetch_pumps.read().await.contains_key(tab_id) {
+            session.send("Fetch.enable", params).await?;
+            return Ok(());
+        }
 
-        // Wait for Chrome to start accepting CDP connections
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let events = session.subscribe();
+        session.send("Fetch.enable", params).await?;
 
-        info!("Chrome launched successfully");
+        let pump = tokio::spawn(pump_fetch(
+            session.clone(),
+            credentials,
+            self.request_rules.clone(),
+            events,
+        ));
+        self.fetch_pumps
+            .write()
+            .await
+            .insert(tab_id.to_string(), pump);
         Ok(())
     }
 
+    /// Reports the session's security state and certificate errors on its tab.
+    async fn start_security_monitoring(&self, tab_id: &str, session: &CdpSession) -> Result<()> {
+        let events = session.subscribe();
+        session.send("Security.enable", json!({})).await?;
+        session
+            .send(
+                "Security.setOverrideCertificateErrors",
+                json!({ "override": true }),
+            )
+            .await?;
+        // Blocked navigations are told apart from subresources by their request
+        session.send("Network.enable", json!({})).await?;
+
+        let pump = tokio::spawn(pump_security(
+            tab_id.to_string(),
+            session.clone(),
+            self.tabs.clone(),
+            self.allowed_cert_hosts.clone(),
+            events,
+        ));
+        if let Some(previous) = self
+            .security_pumps
+            .write()
+            .await
+            .insert(tab_id.to_string(), pump)
+        {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Reapplies the site's zoom as each new document in the tab loads.
+    async fn start_navigation_watch(&self, tab_id: &str, session: &CdpSession) -> Result<()> {
+        let events = session.subscribe();
+        session.send("Page.enable", json!({})).await?;
+
+        let pump = tokio::spawn(pump_navigations(
+            tab_id.to_string(),
+            session.clone(),
+            self.zoom_levels.clone(),
+            self.isolated_worlds.clone(),
+            events,
+        ));
+        if let Some(previous) = self
+            .navigation_pumps
+            .write()
+            .await
+            .insert(tab_id.to_string(), pump)
+        {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Closes the Chrome target behind a tab, if one was ever opened.
+    async fn close_target(&self, tab_id: &str) {
+        if let Some((conn, target_id)) = self.detach_target(tab_id).await {
+            close_chrome_target(&conn, tab_id, &target_id).await;
+        }
+    }
+
+    /// Forgets the Chrome target behind a tab and everything tied to it, returning what
+    /// `close_chrome_target` needs. Closing waits on Chrome, so callers holding the
+    /// manager can release it first.
+    async fn detach_target(&self, tab_id: &str) -> Option<(Arc<CdpConnection>, String)> {
+        let session = self.sessions.write().await.remove(tab_id)?;
+        self.file_chooser_files.write().await.remove(tab_id);
+        if let Some(pump) = self.file_chooser_pumps.write().await.remove(tab_id) {
+            pump.abort();
+        }
+        self.find_sessions.write().await.remove(tab_id);
+        self.marks.write().await.remove(tab_id);
+        if let Some(pump) = self.screencasts.write().await.remove(tab_id) {
+            pump.abort();
+        }
+        if let Some(pump) = self.fetch_pumps.write().await.remove(tab_id) {
+            pump.abort();
+        }
+        if let Some(pump) = self.security_pumps.write().await.remove(tab_id) {
+            pump.abort();
+        }
+        if let Some(pump) = self.navigation_pumps.write().await.remove(tab_id) {
+            pump.abort();
+        }
+        self.isolated_worlds.write().await.remove(tab_id);
+
+        let conn = self.cdp.read().await.clone()?;
+        Some((conn, session.target_id))
+    }
+
     fn find_chrome(&self) -> Result<std::path::PathBuf> {
         #[cfg(target_os = "windows")]
         {
@@ -113,8 +523,12 @@ impl BrowserManager {
         #[cfg(target_os = "macos")]
         {
             let paths = vec![
-                std::path::PathBuf::from("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"),
-                std::path::PathBuf::from("/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge"),
+                std::path::PathBuf::from(
+                    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+                ),
+                std::path::PathBuf::from(
+                    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+                ),
             ];
 
             for path in paths {
@@ -126,7 +540,12 @@ impl BrowserManager {
 
         #[cfg(target_os = "linux")]
         {
-            let candidates = vec!["google-chrome", "chromium", "chromium-browser", "microsoft-edge"];
+            let candidates = vec![
+                "google-chrome",
+                "chromium",
+                "chromium-browser",
+                "microsoft-edge",
+            ];
             for cmd in candidates {
                 if let Ok(path) = which::which(cmd) {
                     return Ok(path);
@@ -139,7 +558,38 @@ impl BrowserManager {
         ))
     }
 
-    pub async fn create_tab(&self, url: Option<String>) -> Result<Tab> {
+    /// Opens a tab in the focused window and switches to it.
+    pub async fn create_tab(
+        &self,
+        url: Option<String>,
+        browser_context_id: Option<String>,
+    ) -> Result<Tab> {
+        let window_id = self.focused_window().await;
+        self.create_tab_in_window(&window_id, url, browser_context_id)
+            .await
+    }
+
+    pub async fn create_tab_in_window(
+        &self,
+        window_id: &str,
+        url: Option<String>,
+        browser_context_id: Option<String>,
+    ) -> Result<Tab> {
+        if !self.windows.read().await.iter().any(|w| w == window_id) {
+            return Err(BrowserError::NotFound(format!(
+                "Window {} not found",
+                window_id
+            )));
+        }
+        if let Some(context_id) = &browser_context_id {
+            if !self.browser_contexts.read().await.contains_key(context_id) {
+                return Err(BrowserError::NotFound(format!(
+                    "Browser context {} not found",
+                    context_id
+                )));
+            }
+        }
+
         let tab_id = uuid::Uuid::new_v4().to_string();
         let url = url.unwrap_or_else(|| "about:blank".to_string());
 
@@ -148,125 +598,4217 @@ impl BrowserManager {
             url: url.clone(),
             title: "New Tab".to_string(),
             favicon: None,
-            position: self.get_next_position().await,
+            window_id: window_id.to_string(),
+            position: self.get_next_position(window_id).await,
             is_active: true,
+            lifecycle_state: LifecycleState::Active,
+            opener_id: None,
+            browser_context_id,
+            group_id: None,
+            pinned: false,
+            security: TabSecurity::default(),
         };
 
-        // Deactivate current active tab
-        if let Some(active_id) = self.active_tab.read().await.clone() {
-            let mut tabs = self.tabs.write().await;
-            if let Some(t) = tabs.get_mut(&active_id) {
-                t.is_active = false;
-            }
-        }
+        // Deactivate the window's current tab
+        let mut tabs = self.tabs.write().await;
+        self.deactivate_window(&mut tabs, window_id).await;
 
         // Activate new tab
-        let mut active = self.active_tab.write().await;
-        *active = Some(tab_id.clone());
+        *self.active_tab.write().await = Some(tab_id.clone());
+        self.mark_recent(&tab_id).await;
 
         // Store tab
-        self.tabs.write().await.insert(tab_id.clone(), tab.clone());
+        tabs.insert(tab_id.clone(), tab.clone());
+        drop(tabs);
+        emit(BrowserEvent::TabCreated { tab: tab.clone() });
 
         info!("Created tab {} with URL {}", tab_id, url);
         Ok(tab)
     }
 
     pub async fn close_tab(&self, tab_id: &str) -> Result<()> {
+        self.remove_tab(tab_id, true).await
+    }
+
+    /// Closes a tab, adding it to the recently closed tabs when `remember` is set.
+    async fn remove_tab(&self, tab_id: &str, remember: bool) -> Result<()> {
+        // Read the back/forward list while the page is still there
+        let history = if remember {
+            self.navigation_history(tab_id).await
+        } else {
+            None
+        };
         let mut tabs = self.tabs.write().await;
 
-        if tabs.remove(tab_id).is_none() {
+        let Some(closed) = tabs.remove(tab_id) else {
             return Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)));
+        };
+        normalize_positions(&mut tabs);
+        if closed.group_id.is_some() {
+            for group_id in remove_empty_groups(&tabs, &mut *self.groups.write().await) {
+                emit(BrowserEvent::TabGroupRemoved { group_id });
+            }
         }
 
-        // If we closed the active tab, activate another one
-        let mut active = self.active_tab.write().await;
-        if active.as_deref() == Some(tab_id) {
-            *active = tabs.keys().next().cloned();
-            if let Some(new_active) = active.as_ref() {
-                if let Some(t) = tabs.get_mut(new_active) {
-                    t.is_active = true;
-                }
-            }
+        // Private tabs leave no trace
+        if remember && closed.browser_context_id.is_none() {
+            let (history, history_index) = history.unwrap_or_else(|| {
+                let entry = NavigationEntry {
+                    url: closed.url.clone(),
+                    title: closed.title.clone(),
+                };
+                (vec![entry], 0)
+            });
+            let mut closed_tabs = self.closed_tabs.write().await;
+            closed_tabs.push_front(ClosedTab::new(&closed, history, history_index));
+            closed_tabs.truncate(MAX_CLOSED_TABS);
+            emit(BrowserEvent::ClosedTabsChanged);
+        }
+
+        let target = self.detach_target(tab_id).await;
+        self.inactive_since.write().await.remove(tab_id);
+        self.discarded_scroll.write().await.remove(tab_id);
+        self.find_sessions.write().await.remove(tab_id);
+        self.network_emulation.write().await.remove(tab_id);
+
+        emit(BrowserEvent::TabClosed {
+            tab_id: tab_id.to_string(),
+        });
+
+        // If we closed its window's active tab, activate another one there
+        self.recent_tabs.write().await.retain(|id| id != tab_id);
+        if closed.is_active {
+            self.activate_successor(&mut tabs, &closed).await;
+        }
+        self.close_window_if_empty(&tabs, &closed.window_id).await;
+        drop(tabs);
+
+        // Chrome is asked to close the target once readers of the tabs are let go
+        if let Some((conn, target_id)) = target {
+            close_chrome_target(&conn, tab_id, &target_id).await;
         }
 
         info!("Closed tab {}", tab_id);
         Ok(())
     }
 
-    pub async fn switch_tab(&self, tab_id: &str) -> Result<Tab> {
-        let mut tabs = self.tabs.write().await;
+    async fn navigation_history(&self, tab_id: &str) -> Option<(Vec<NavigationEntry>, usize)> {
+        let session = self.sessions.read().await.get(tab_id).cloned()?;
+        let result = session
+            .send("Page.getNavigationHistory", json!({}))
+            .await
+            .ok()?;
+        Some(navigation_entries(&result)).filter(|(entries, _)| !entries.is_empty())
+    }
 
-        // Deactivate current
-        if let Some(active_id) = self.active_tab.read().await.clone() {
-            if let Some(t) = tabs.get_mut(&active_id) {
-                t.is_active = false;
+    pub async fn get_recently_closed_tabs(&self) -> Vec<ClosedTab> {
+        self.closed_tabs.read().await.iter().cloned().collect()
+    }
+
+    /// Reopens a closed tab where it was and switches to it.
+    ///
+    /// `closed_id` picks an entry from the recently closed list; `None` takes the most
+    /// recent. The tab rejoins its group if the group is still there and the tab lands
+    /// next to it.
+    pub async fn reopen_closed_tab(&self, closed_id: Option<&str>) -> Result<ReopenedTab> {
+        let closed = {
+            let mut closed_tabs = self.closed_tabs.write().await;
+            let index = match closed_id {
+                Some(id) => closed_tabs.iter().position(|c| c.id == id),
+                None => (!closed_tabs.is_empty()).then_some(0),
+            };
+            index
+                .and_then(|i| closed_tabs.remove(i))
+                .ok_or_else(|| BrowserError::NotFound("No closed tab to reopen".to_string()))?
+        };
+        emit(BrowserEvent::ClosedTabsChanged);
+
+        // The tab's window may have closed since
+        let window_id = if self.windows.read().await.contains(&closed.window_id) {
+            closed.window_id.clone()
+        } else {
+            self.focused_window().await
+        };
+
+        let tab_id = uuid::Uuid::new_v4().to_string();
+        {
+            let mut tabs = self.tabs.write().await;
+            let groups = self.groups.read().await;
+
+            let mut tab = Tab {
+                id: tab_id.clone(),
+                url: closed.url.clone(),
+                title: closed.title.clone(),
+                favicon: closed.favicon.clone(),
+                window_id: window_id.clone(),
+                position: closed.position,
+                is_active: false,
+                lifecycle_state: LifecycleState::Active,
+                opener_id: None,
+                browser_context_id: None,
+                group_id: closed.group_id.clone().filter(|g| groups.contains_key(g)),
+                pinned: closed.pinned,
+                security: TabSecurity::default(),
+            };
+            for t in tabs.values_mut().filter(|t| t.window_id == window_id) {
+                if t.position >= tab.position {
+                    t.position += 1;
+                }
             }
+            tabs.insert(tab_id.clone(), tab.clone());
+
+            let order = window_order(&mut tabs, &window_id);
+            let index = order
+                .iter()
+                .position(|id| *id == tab_id)
+                .unwrap_or_default();
+            tab.group_id = group_after_move(&tabs, &order, index);
+            tab.position = index as i32;
+            tabs.insert(tab_id.clone(), tab.clone());
+            emit(BrowserEvent::TabCreated { tab });
         }
 
+        let tab = self.switch_tab(&tab_id).await?;
+        info!("Reopened closed tab {} as {}", closed.id, tab_id);
+        Ok(ReopenedTab {
+            tab,
+            history: closed.history,
+            history_index: closed.history_index,
+        })
+    }
+
+    pub async fn switch_tab(&self, tab_id: &str) -> Result<Tab> {
+        let mut tabs = self.tabs.write().await;
+
+        let Some((state, window_id)) = tabs
+            .get(tab_id)
+            .map(|t| (t.lifecycle_state, t.window_id.clone()))
+        else {
+            return Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)));
+        };
+
+        // Deactivate the window's current tab
+        self.deactivate_window(&mut tabs, &window_id).await;
+
         // Activate new
         if let Some(t) = tabs.get_mut(tab_id) {
             t.is_active = true;
-            let mut active = self.active_tab.write().await;
-            *active = Some(tab_id.to_string());
-            info!("Switched to tab {}", tab_id);
-            Ok(t.clone())
-        } else {
-            Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)))
         }
+        *self.active_tab.write().await = Some(tab_id.to_string());
+        self.inactive_since.write().await.remove(tab_id);
+        self.mark_recent(tab_id).await;
+        drop(tabs);
+
+        if state != LifecycleState::Active {
+            self.wake_tab(tab_id, state).await?;
+        }
+        emit(BrowserEvent::TabActivated {
+            tab_id: tab_id.to_string(),
+        });
+
+        info!("Switched to tab {}", tab_id);
+        self.tabs
+            .read()
+            .await
+            .get(tab_id)
+            .cloned()
+            .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))
     }
 
     pub async fn navigate_tab(&self, tab_id: &str, url: &str) -> Result<Tab> {
-        let mut tabs = self.tabs.write().await;
-
-        if let Some(t) = tabs.get_mut(tab_id) {
+        let tab = {
+            let mut tabs = self.tabs.write().await;
+            let t = tabs
+                .get_mut(tab_id)
+                .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
             t.url = url.to_string();
-            info!("Navigated tab {} to {}", tab_id, url);
-            Ok(t.clone())
-        } else {
-            Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)))
+            t.security.certificate_error = None;
+            t.clone()
+        };
+
+        // Tabs without a target yet will open on the new URL when first attached
+        let session = self.sessions.read().await.get(tab_id).cloned();
+        if let Some(session) = session {
+            session.send("Page.navigate", json!({ "url": url })).await?;
         }
+
+        emit(BrowserEvent::Navigated {
+            tab_id: tab_id.to_string(),
+            url: url.to_string(),
+        });
+
+        info!("Navigated tab {} to {}", tab_id, url);
+        Ok(tab)
     }
 
     pub async fn get_tabs(&self) -> Vec<Tab> {
         let tabs = self.tabs.read().await;
+        let windows = self.windows.read().await;
         let mut tab_list: Vec<Tab> = tabs.values().cloned().collect();
-        tab_list.sort_by_key(|t| t.position);
+        tab_list.sort_by_key(|t| {
+            let window = windows.iter().position(|w| *w == t.window_id);
+            (window, t.position)
+        });
         tab_list
     }
 
+    /// Returns one window's tabs in tab strip order.
+    pub async fn get_window_tabs(&self, window_id: &str) -> Vec<Tab> {
+        let mut tabs = self.get_tabs().await;
+        tabs.retain(|t| t.window_id == window_id);
+        tabs
+    }
+
     pub async fn get_active_tab(&self) -> Option<Tab> {
         let active_id = self.active_tab.read().await.clone()?;
         self.tabs.read().await.get(&active_id).cloned()
     }
 
-    async fn get_next_position(&self) -> i32 {
+    /// Returns every tab, most recently used first, for a Ctrl+Tab switcher.
+    ///
+    /// Tabs never activated in this run follow in tab strip order.
+    pub async fn get_recent_tabs(&self) -> Vec<Tab> {
+        let recent = self.recent_tabs.read().await.clone();
+        let mut tabs = self.get_tabs().await;
+        tabs.sort_by_key(|t| {
+            recent
+                .iter()
+                .position(|id| *id == t.id)
+                .unwrap_or(recent.len())
+        });
+        tabs
+    }
+
+    pub async fn set_activation_policy(&self, policy: ActivationPolicy) {
+        *self.activation_policy.write().await = policy;
+    }
+
+    async fn mark_recent(&self, tab_id: &str) {
+        let mut recent = self.recent_tabs.write().await;
+        recent.retain(|id| id != tab_id);
+        recent.insert(0, tab_id.to_string());
+    }
+
+    /// The window holding the active tab, or the main window when there is none.
+    async fn focused_window(&self) -> WindowId {
+        let active_id = self.active_tab.read().await.clone();
         let tabs = self.tabs.read().await;
-        tabs.len() as i32
+        active_id
+            .and_then(|id| tabs.get(&id).map(|t| t.window_id.clone()))
+            .unwrap_or_else(|| MAIN_WINDOW.to_string())
     }
-}
 
-impl Drop for BrowserManager {
-    fn drop(&mut self) {
-        if let Some(mut child) = self.chrome_process.take() {
-            let _ = child.kill();
+    async fn deactivate_window(&self, tabs: &mut HashMap<TabId, Tab>, window_id: &str) {
+        let mut inactive_since = self.inactive_since.write().await;
+        for t in tabs.values_mut() {
+            if t.window_id == window_id && t.is_active {
+                t.is_active = false;
+                inactive_since.insert(t.id.clone(), Instant::now());
+            }
         }
     }
-}
 
-// Global browser manager instance
-use once_cell::sync::Lazy;
-use std::sync::Mutex as StdMutex;
+    /// Activates the tab that takes over from `leaving` in its window, which no longer
+    /// holds it.
+    async fn activate_successor(&self, tabs: &mut HashMap<TabId, Tab>, leaving: &Tab) {
+        let window_tabs: HashMap<TabId, Tab> = tabs
+            .values()
+            .filter(|t| t.window_id == leaving.window_id)
+            .map(|t| (t.id.clone(), t.clone()))
+            .collect();
+        let mut recent = self.recent_tabs.write().await;
+        let policy = *self.activation_policy.read().await;
+        let successor = next_active_tab(leaving, &window_tabs, &recent, policy);
 
-static BROWSER_MANAGER: Lazy<StdMutex<Option<Arc<Mutex<BrowserManager>>>>> =
-    Lazy::new(|| StdMutex::new(None));
+        if let Some(new_active) = &successor {
+            if let Some(t) = tabs.get_mut(new_active) {
+                t.is_active = true;
+            }
+            recent.retain(|id| id != new_active);
+            recent.insert(0, new_active.clone());
+            self.inactive_since.write().await.remove(new_active);
+            emit(BrowserEvent::TabActivated {
+                tab_id: new_active.clone(),
+            });
+        }
 
-pub fn init_browser_manager() -> Arc<Mutex<BrowserManager>> {
-    let manager = Arc::new(Mutex::new(BrowserManager::new()));
-    let mut global = BROWSER_MANAGER.lock().unwrap();
-    *global = Some(manager.clone());
-    manager
-}
+        let mut active = self.active_tab.write().await;
+        if active.as_deref() == Some(leaving.id.as_str()) {
+            // An emptied window hands focus to the active tab of another one
+            *active = successor.or_else(|| {
+                recent
+                    .iter()
+                    .find(|id| tabs.get(*id).is_some_and(|t| t.is_active))
+                    .cloned()
+            });
+        }
+    }
 
-pub fn get_browser_manager() -> Option<Arc<Mutex<BrowserManager>>> {
-    BROWSER_MANAGER.lock().unwrap().clone()
+    /// Closes a window other than the main one once its last tab has gone.
+    async fn close_window_if_empty(&self, tabs: &HashMap<TabId, Tab>, window_id: &str) {
+        if window_id == MAIN_WINDOW || tabs.values().any(|t| t.window_id == window_id) {
+            return;
+        }
+        let mut windows = self.windows.write().await;
+        if windows.iter().any(|w| w == window_id) {
+            windows.retain(|w| w != window_id);
+            emit(BrowserEvent::WindowClosed {
+                window_id: window_id.to_string(),
+            });
+            info!("Closed window {}", window_id);
+        }
+    }
+
+    pub async fn get_windows(&self) -> Vec<BrowserWindow> {
+        let tabs = self.tabs.read().await;
+        self.windows
+            .read()
+            .await
+            .iter()
+            .map(|id| BrowserWindow {
+                id: id.clone(),
+                active_tab: tabs
+                    .values()
+                    .find(|t| t.window_id == *id && t.is_active)
+                    .map(|t| t.id.clone()),
+                tab_count: tabs.values().filter(|t| t.window_id == *id).count(),
+            })
+            .collect()
+    }
+
+    /// Opens a new window, moving `tab_id` into it or else starting it with a blank tab.
+    pub async fn create_window(&self, tab_id: Option<&str>) -> Result<BrowserWindow> {
+        if let Some(tab_id) = tab_id {
+            if !self.tabs.read().await.contains_key(tab_id) {
+                return Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)));
+            }
+        }
+
+        let window_id = new_window_id();
+        self.windows.write().await.push(window_id.clone());
+        emit(BrowserEvent::WindowCreated {
+            window_id: window_id.clone(),
+        });
+        match tab_id {
+            Some(tab_id) => {
+                self.move_tab_to_window(tab_id, &window_id, None).await?;
+            }
+            None => {
+                self.create_tab_in_window(&window_id, None, None).await?;
+            }
+        }
+
+        info!("Created window {}", window_id);
+        self.get_windows()
+            .await
+            .into_iter()
+            .find(|w| w.id == window_id)
+            .ok_or_else(|| BrowserError::NotFound(format!("Window {} not found", window_id)))
+    }
+
+    /// Closes a window and every tab in it. The main window can't be closed.
+    pub async fn close_window(&self, window_id: &str) -> Result<()> {
+        if window_id == MAIN_WINDOW {
+            return Err(BrowserError::Configuration(
+                "The main window can't be closed".to_string(),
+            ));
+        }
+        if !self.windows.read().await.iter().any(|w| w == window_id) {
+            return Err(BrowserError::NotFound(format!(
+                "Window {} not found",
+                window_id
+            )));
+        }
+
+        // Closing the last tab closes the window too
+        for tab in self.get_window_tabs(window_id).await {
+            self.close_tab(&tab.id).await?;
+        }
+        self.close_window_if_empty(&*self.tabs.read().await, window_id)
+            .await;
+        Ok(())
+    }
+
+    /// Makes `window_id` the focused window, so its active tab becomes the active tab.
+    pub async fn focus_window(&self, window_id: &str) {
+        let active_id = self
+            .tabs
+            .read()
+            .await
+            .values()
+            .find(|t| t.window_id == window_id && t.is_active)
+            .map(|t| t.id.clone());
+        if let Some(active_id) = active_id {
+            *self.active_tab.write().await = Some(active_id.clone());
+            self.mark_recent(&active_id).await;
+        }
+    }
+
+    /// Moves a tab to another window at `index` in its tab strip, or at the end, and
+    /// switches to it there.
+    ///
+    /// The tab leaves its group. A window other than the main one closes when its last
+    /// tab moves out.
+    pub async fn move_tab_to_window(
+        &self,
+        tab_id: &str,
+        window_id: &str,
+        index: Option<usize>,
+    ) -> Result<Tab> {
+        if !self.windows.read().await.iter().any(|w| w == window_id) {
+            return Err(BrowserError::NotFound(format!(
+                "Window {} not found",
+                window_id
+            )));
+        }
+
+        {
+            let mut tabs = self.tabs.write().await;
+            let leaving = tabs
+                .get(tab_id)
+                .cloned()
+                .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
+            if leaving.window_id == window_id {
+                drop(tabs);
+                if let Some(index) = index {
+                    self.move_tab(tab_id, index).await?;
+                }
+                return self.switch_tab(tab_id).await;
+            }
+
+            if let Some(tab) = tabs.get_mut(tab_id) {
+                tab.window_id = window_id.to_string();
+                tab.group_id = None;
+                tab.is_active = false;
+            }
+            let source_order = window_order(&mut tabs, &leaving.window_id);
+            if leaving.is_active {
+                self.activate_successor(&mut tabs, &leaving).await;
+            }
+            let order = place_tabs(
+                &mut tabs,
+                &[tab_id.to_string()],
+                index.unwrap_or(usize::MAX),
+                true,
+            );
+
+            if leaving.group_id.is_some() {
+                for group_id in remove_empty_groups(&tabs, &mut *self.groups.write().await) {
+                    emit(BrowserEvent::TabGroupRemoved { group_id });
+                }
+            }
+            emit(BrowserEvent::TabUpdated {
+                tab: tabs[tab_id].clone(),
+            });
+            emit(BrowserEvent::TabsReordered {
+                window_id: leaving.window_id.clone(),
+                tab_ids: source_order,
+            });
+            emit(BrowserEvent::TabsReordered {
+                window_id: window_id.to_string(),
+                tab_ids: order,
+            });
+            self.close_window_if_empty(&tabs, &leaving.window_id).await;
+        }
+
+        info!("Moved tab {} to window {}", tab_id, window_id);
+        self.switch_tab(tab_id).await
+    }
+
+    /// Reopens tabs, groups and windows saved from a previous run. The tabs load when
+    /// first shown.
+    ///
+    /// Tabs whose window wasn't saved go to the main window.
+    pub async fn restore_tabs(
+        &self,
+        saved: Vec<Tab>,
+        saved_groups: Vec<TabGroup>,
+        saved_windows: Vec<WindowId>,
+    ) {
+        if saved.is_empty() {
+            return;
+        }
+
+        let mut windows = self.windows.write().await;
+        for window_id in saved_windows {
+            if !windows.contains(&window_id) {
+                windows.push(window_id);
+            }
+        }
+
+        let mut tabs = self.tabs.write().await;
+        for mut tab in saved {
+            if !windows.contains(&tab.window_id) {
+                tab.window_id = MAIN_WINDOW.to_string();
+            }
+            tabs.insert(tab.id.clone(), tab);
+        }
+        // Older saves can have gaps or repeats, so renumber before announcing them
+        normalize_positions(&mut tabs);
+        windows.retain(|w| w == MAIN_WINDOW || tabs.values().any(|t| t.window_id == *w));
+
+        // One active tab per window: the saved one, else the window's first
+        let mut inactive_since = self.inactive_since.write().await;
+        for window_id in windows.iter() {
+            let order = window_order(&mut tabs, window_id);
+            let window_active = order
+                .iter()
+                .find(|id| tabs[*id].is_active)
+                .or(order.first())
+                .cloned();
+            for id in order {
+                if let Some(tab) = tabs.get_mut(&id) {
+                    tab.is_active = Some(&id) == window_active.as_ref();
+                    if !tab.is_active {
+                        inactive_since.insert(id, Instant::now());
+                    }
+                }
+            }
+        }
+        let active_id = windows
+            .iter()
+            .find_map(|w| tabs.values().find(|t| t.window_id == *w && t.is_active))
+            .map(|t| t.id.clone());
+
+        let mut groups = self.groups.write().await;
+        groups.extend(saved_groups.into_iter().map(|g| (g.id.clone(), g)));
+        remove_empty_groups(&tabs, &mut groups);
+        for tab in tabs.values_mut() {
+            if tab
+                .group_id
+                .as_ref()
+                .is_some_and(|g| !groups.contains_key(g))
+            {
+                tab.group_id = None;
+            }
+        }
+        for window_id in windows.iter().filter(|w| *w != MAIN_WINDOW) {
+            emit(BrowserEvent::WindowCreated {
+                window_id: window_id.clone(),
+            });
+        }
+        for group in groups.values() {
+            emit(BrowserEvent::TabGroupUpdated {
+                group: group.clone(),
+            });
+        }
+        for tab in tabs.values() {
+            emit(BrowserEvent::TabCreated { tab: tab.clone() });
+        }
+        *self.recent_tabs.write().await = active_id.iter().cloned().collect();
+        *self.active_tab.write().await = active_id;
+
+        info!("Restored {} tabs from the previous session", tabs.len());
+    }
+
+    /// Opens a saved set of tabs after the focused window's tabs and switches to the first.
+    ///
+    /// With `replace`, the tabs that were open in that window before are closed
+    /// afterwards, except pinned ones. They aren't added to the recently closed tabs,
+    /// which would otherwise fill up with the tabs the session replaced.
+    pub async fn open_tab_set(&self, saved: Vec<SessionTab>, replace: bool) -> Result<Vec<Tab>> {
+        if saved.is_empty() {
+            return Err(BrowserError::NotFound("Session has no tabs".to_string()));
+        }
+
+        let window_id = self.focused_window().await;
+        let previous: Vec<TabId> = self
+            .tabs
+            .read()
+            .await
+            .values()
+            .filter(|t| t.window_id == window_id && !t.pinned)
+            .map(|t| t.id.clone())
+            .collect();
+        let mut position = self.get_next_position(&window_id).await;
+
+        let mut opened = Vec::with_capacity(saved.len());
+        {
+            let mut tabs = self.tabs.write().await;
+            for saved_tab in saved {
+                let tab = Tab {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    url: saved_tab.url,
+                    title: saved_tab.title,
+                    favicon: saved_tab.favicon,
+                    window_id: window_id.clone(),
+                    position,
+                    is_active: false,
+                    lifecycle_state: LifecycleState::Active,
+                    opener_id: None,
+                    browser_context_id: None,
+                    group_id: None,
+                    pinned: false,
+                    security: TabSecurity::default(),
+                };
+                position += 1;
+                emit(BrowserEvent::TabCreated { tab: tab.clone() });
+                tabs.insert(tab.id.clone(), tab.clone());
+                opened.push(tab.id);
+            }
+        }
+
+        self.switch_tab(&opened[0]).await?;
+        if replace {
+            for tab_id in previous {
+                self.remove_tab(&tab_id, false).await?;
+            }
+        }
+
+        info!("Opened {} saved tabs", opened.len());
+        let tabs = self.tabs.read().await;
+        Ok(opened
+            .iter()
+            .filter_map(|id| tabs.get(id).cloned())
+            .collect())
+    }
+
+    /// Moves a tab to `new_index` in its window's tab strip, shifting the tabs in between.
+    ///
+    /// Indexes past the end move the tab to the end. Dropping a tab inside a group adds
+    /// it to the group, and dragging it away from its group takes it out. Returns the
+    /// tabs in their new order.
+    pub async fn move_tab(&self, tab_id: &str, new_index: usize) -> Result<Vec<Tab>> {
+        {
+            let mut tabs = self.tabs.write().await;
+            if !tabs.contains_key(tab_id) {
+                return Err(BrowserError::NotFound(format!("Tab {} not found", tab_id)));
+            }
+
+            let order = place_tabs(&mut tabs, &[tab_id.to_string()], new_index, false);
+            let index = order.iter().position(|id| id == tab_id).unwrap_or_default();
+            let group_id = group_after_move(&tabs, &order, index);
+
+            let mut groups = self.groups.write().await;
+            if let Some(tab) = tabs.get_mut(tab_id) {
+                if tab.group_id != group_id {
+                    tab.group_id = group_id;
+                    emit(BrowserEvent::TabUpdated { tab: tab.clone() });
+                }
+            }
+            for group_id in remove_empty_groups(&tabs, &mut groups) {
+                emit(BrowserEvent::TabGroupRemoved { group_id });
+            }
+            emit(BrowserEvent::TabsReordered {
+                window_id: tabs[tab_id].window_id.clone(),
+                tab_ids: order,
+            });
+        }
+
+        debug!("Moved tab {} to index {}", tab_id, new_index);
+        Ok(self.get_tabs().await)
+    }
+
+    /// Returns the groups in the order they appear in the tab strip.
+    pub async fn get_tab_groups(&self) -> Vec<TabGroup> {
+        let tabs = self.get_tabs().await;
+        let groups = self.groups.read().await;
+
+        let mut seen = HashSet::new();
+        tabs.iter()
+            .filter_map(|t| t.group_id.as_ref())
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| groups.get(id).cloned())
+            .collect()
+    }
+
+    /// Groups the given tabs, pulling them together where the first of them sits.
+    ///
+    /// The tabs must share a window. Tabs already in another group leave it, and pinned
+    /// tabs are unpinned.
+    pub async fn create_tab_group(
+        &self,
+        tab_ids: &[TabId],
+        title: String,
+        color: GroupColor,
+    ) -> Result<TabGroup> {
+        let group = TabGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            title,
+            color,
+            collapsed: false,
+        };
+
+        {
+            let mut tabs = self.tabs.write().await;
+            if tab_ids.is_empty() {
+                return Err(BrowserError::NotFound("No tabs to group".to_string()));
+            }
+            if let Some(missing) = tab_ids.iter().find(|id| !tabs.contains_key(*id)) {
+                return Err(BrowserError::NotFound(format!("Tab {} not found", missing)));
+            }
+
+            let window_id = tabs[&tab_ids[0]].window_id.clone();
+            if tab_ids.iter().any(|id| tabs[id].window_id != window_id) {
+                return Err(BrowserError::Configuration(
+                    "Grouped tabs must be in the same window".to_string(),
+                ));
+            }
+
+            let mut members = window_order(&mut tabs, &window_id);
+            members.retain(|id| tab_ids.contains(id));
+            let index = tabs[&members[0]].position as usize;
+            for id in &members {
+                if let Some(tab) = tabs.get_mut(id) {
+                    tab.group_id = Some(group.id.clone());
+                    tab.pinned = false;
+                }
+            }
+            let order = place_tabs(&mut tabs, &members, index, true);
+
+            let mut groups = self.groups.write().await;
+            groups.insert(group.id.clone(), group.clone());
+            for group_id in remove_empty_groups(&tabs, &mut groups) {
+                emit(BrowserEvent::TabGroupRemoved { group_id });
+            }
+            emit(BrowserEvent::TabGroupUpdated {
+                group: group.clone(),
+            });
+            for id in &members {
+                emit(BrowserEvent::TabUpdated {
+                    tab: tabs[id].clone(),
+                });
+            }
+            emit(BrowserEvent::TabsReordered {
+                window_id,
+                tab_ids: order,
+            });
+        }
+
+        info!("Grouped {} tabs as {}", tab_ids.len(), group.id);
+        Ok(group)
+    }
+
+    /// Renames, recolors, collapses or expands a group.
+    pub async fn update_tab_group(
+        &self,
+        group_id: &str,
+        update: TabGroupUpdate,
+    ) -> Result<TabGroup> {
+        let mut groups = self.groups.write().await;
+        let group = groups
+            .get_mut(group_id)
+            .ok_or_else(|| BrowserError::NotFound(format!("Group {} not found", group_id)))?;
+        group.apply(update);
+
+        emit(BrowserEvent::TabGroupUpdated {
+            group: group.clone(),
+        });
+        Ok(group.clone())
+    }
+
+    /// Dissolves a group, leaving its tabs where they are.
+    pub async fn ungroup_tabs(&self, group_id: &str) -> Result<()> {
+        let mut tabs = self.tabs.write().await;
+        if self.groups.write().await.remove(group_id).is_none() {
+            return Err(BrowserError::NotFound(format!(
+                "Group {} not found",
+                group_id
+            )));
+        }
+
+        for tab in tabs.values_mut() {
+            if tab.group_id.as_deref() == Some(group_id) {
+                tab.group_id = None;
+                emit(BrowserEvent::TabUpdated { tab: tab.clone() });
+            }
+        }
+        emit(BrowserEvent::TabGroupRemoved {
+            group_id: group_id.to_string(),
+        });
+
+        info!("Ungrouped {}", group_id);
+        Ok(())
+    }
+
+    /// Moves a whole group so its first tab lands at `new_index`, counted among the
+    /// tabs outside the group. Returns the tabs in their new order.
+    pub async fn move_tab_group(&self, group_id: &str, new_index: usize) -> Result<Vec<Tab>> {
+        {
+            let mut tabs = self.tabs.write().await;
+            if !self.groups.read().await.contains_key(group_id) {
+                return Err(BrowserError::NotFound(format!(
+                    "Group {} not found",
+                    group_id
+                )));
+            }
+
+            let mut members = normalize_positions(&mut tabs);
+            members.retain(|id| tabs[id].group_id.as_deref() == Some(group_id));
+            let order = place_tabs(&mut tabs, &members, new_index, true);
+            emit(BrowserEvent::TabsReordered {
+                window_id: tabs[&members[0]].window_id.clone(),
+                tab_ids: order,
+            });
+        }
+
+        debug!("Moved group {} to index {}", group_id, new_index);
+        Ok(self.get_tabs().await)
+    }
+
+    /// Pins or unpins a tab. Pinning moves it to the end of the pinned tabs and takes
+    /// it out of its group; unpinning moves it to just after them.
+    pub async fn set_tab_pinned(&self, tab_id: &str, pinned: bool) -> Result<Tab> {
+        let tab = {
+            let mut tabs = self.tabs.write().await;
+            let tab = tabs
+                .get_mut(tab_id)
+                .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
+            if tab.pinned == pinned {
+                return Ok(tab.clone());
+            }
+            tab.pinned = pinned;
+            if pinned {
+                tab.group_id = None;
+            }
+
+            // Its old position already lies past the pinned tabs, or before the others
+            let window_id = tab.window_id.clone();
+            let order = window_order(&mut tabs, &window_id);
+            for group_id in remove_empty_groups(&tabs, &mut *self.groups.write().await) {
+                emit(BrowserEvent::TabGroupRemoved { group_id });
+            }
+            let tab = tabs[tab_id].clone();
+            emit(BrowserEvent::TabUpdated { tab: tab.clone() });
+            emit(BrowserEvent::TabsReordered {
+                window_id,
+                tab_ids: order,
+            });
+            tab
+        };
+
+        info!(
+            "{} tab {}",
+            if pinned { "Pinned" } else { "Unpinned" },
+            tab_id
+        );
+        Ok(tab)
+    }
+
+    async fn get_next_position(&self, window_id: &str) -> i32 {
+        let tabs = self.tabs.read().await;
+        tabs.values()
+            .filter(|t| t.window_id == window_id)
+            .map(|t| t.position + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub async fn set_lifecycle_settings(&self, settings: LifecycleSettings) {
+        *self.lifecycle_settings.write().await = settings;
+    }
+
+    /// Background tabs idle past the configured limits, with what the sweep should do to
+    /// each.
+    async fn idle_tabs(&self) -> Vec<IdleTab> {
+        let settings = self.lifecycle_settings.read().await.clone();
+        if !settings.enabled {
+            return Vec::new();
+        }
+
+        let tabs = self.tabs.read().await;
+        let sessions = self.sessions.read().await;
+        let inactive_since = self.inactive_since.read().await;
+
+        inactive_since
+            .iter()
+            .filter_map(|(id, since)| {
+                let tab = tabs.get(id)?;
+                let session = sessions.get(id)?;
+                if tab.is_active || tab.lifecycle_state == LifecycleState::Discarded {
+                    return None;
+                }
+
+                // Pinned tabs may be frozen but keep their page
+                let idle = since.elapsed();
+                let action = if idle >= settings.discard_after && !tab.pinned {
+                    IdleAction::Discard
+                } else if idle >= settings.freeze_after
+                    && tab.lifecycle_state == LifecycleState::Active
+                {
+                    IdleAction::Freeze
+                } else {
+                    return None;
+                };
+                Some(IdleTab {
+                    tab_id: id.clone(),
+                    state: tab.lifecycle_state,
+                    action,
+                    session: session.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether a tab is still in the background in `state`, so a transition worked out
+    /// while the manager was unlocked still applies.
+    async fn still_idle(&self, tab_id: &str, state: LifecycleState) -> bool {
+        self.tabs
+            .read()
+            .await
+            .get(tab_id)
+            .is_some_and(|t| !t.is_active && t.lifecycle_state == state)
+    }
+
+    /// Brings a frozen or discarded tab back to life, reloading it if needed.
+    async fn wake_tab(&self, tab_id: &str, state: LifecycleState) -> Result<()> {
+        match state {
+            LifecycleState::Active => {}
+            LifecycleState::Frozen => {
+                // Frozen tabs keep their target
+                let session = self.sessions.read().await.get(tab_id).cloned();
+                let session = session.ok_or_else(|| {
+                    BrowserError::NotFound(format!("Tab {} has no target", tab_id))
+                })?;
+                session
+                    .send("Page.setWebLifecycleState", json!({ "state": "active" }))
+                    .await?;
+            }
+            LifecycleState::Discarded => {
+                let url = self
+                    .tabs
+                    .read()
+                    .await
+                    .get(tab_id)
+                    .map(|t| t.url.clone())
+                    .unwrap_or_default();
+
+                // Attach on a blank page first so the load event can't slip past us. A
+                // target opened for the tab meanwhile is reused rather than leaked.
+                let existing = self.sessions.read().await.get(tab_id).cloned();
+                let session = match existing {
+                    Some(session) => session,
+                    None => self.open_session(tab_id, "about:blank").await?,
+                };
+                let mut events = session.subscribe();
+                session.send("Page.enable", json!({})).await?;
+                session.send("Page.navigate", json!({ "url": url })).await?;
+
+                if let Some((x, y)) = self.discarded_scroll.write().await.remove(tab_id) {
+                    let tab_id = tab_id.to_string();
+                    tokio::spawn(async move {
+                        let loaded = tokio::time::timeout(Duration::from_secs(30), async {
+                            while let Some(event) = recv_event(&mut events).await {
+                                if event.is_for(&session) && event.method == "Page.loadEventFired" {
+                                    return true;
+                                }
+                            }
+                            false
+                        })
+                        .await;
+
+                        if matches!(loaded, Ok(true)) {
+                            let _ = session
+                                .send(
+                                    "Runtime.evaluate",
+                                    json!({ "expression": format!("window.scrollTo({}, {})", x, y) }),
+                                )
+                                .await;
+                            debug!("Restored scroll position for tab {}", tab_id);
+                        }
+                    });
+                }
+            }
+        }
+
+        self.set_lifecycle_state(tab_id, LifecycleState::Active)
+            .await;
+        info!("Woke tab {} from {:?}", tab_id, state);
+        Ok(())
+    }
+
+    async fn set_lifecycle_state(&self, tab_id: &str, state: LifecycleState) {
+        if let Some(t) = self.tabs.write().await.get_mut(tab_id) {
+            t.lifecycle_state = state;
+            emit(BrowserEvent::TabUpdated { tab: t.clone() });
+        }
+    }
+
+    /// Keeps a tab's URL and title in step with what its page is actually showing.
+    pub async fn handle_target_info_changed(&self, target_info: &Value) {
+        let target_id = target_info["targetId"].as_str().unwrap_or_default();
+        let tab_id = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .find(|(_, session)| session.target_id == target_id)
+            .map(|(id, _)| id.clone());
+        let Some(tab_id) = tab_id else {
+            return;
+        };
+
+        let url = target_info["url"].as_str().unwrap_or_default();
+        let title = target_info["title"].as_str().unwrap_or_default();
+
+        let mut tabs = self.tabs.write().await;
+        let Some(t) = tabs.get_mut(&tab_id) else {
+            return;
+        };
+
+        let url_changed = !url.is_empty() && t.url != url;
+        let title_changed = !title.is_empty() && t.title != title;
+        if url_changed {
+            t.url = url.to_string();
+            emit(BrowserEvent::Navigated {
+                tab_id: tab_id.clone(),
+                url: url.to_string(),
+            });
+        }
+        if title_changed {
+            t.title = title.to_string();
+        }
+        if url_changed || title_changed {
+            emit(BrowserEvent::TabUpdated { tab: t.clone() });
+        }
+    }
+
+    /// Collects resource usage for every tab that has a live Chrome target.
+    pub async fn get_tab_metrics(&self) -> Result<Vec<TabMetrics>> {
+        let sessions: Vec<(TabId, CdpSession)> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect();
+
+        // A tab that can't report, e.g. one mid-crash, is left out of the list
+        let mut metrics = Vec::with_capacity(sessions.len());
+        for (tab_id, session) in sessions {
+            match tab_metrics(&tab_id, &session).await {
+                Ok(tab_metrics) => metrics.push(tab_metrics),
+                Err(e) => warn!("Failed to read metrics for tab {}: {}", tab_id, e),
+            }
+        }
+
+        metrics.sort_by_key(|m| std::cmp::Reverse(m.js_heap_used_size));
+        Ok(metrics)
+    }
+
+    /// Lists Chrome's processes, tying each renderer to the tabs it hosts and their
+    /// memory.
+    pub async fn get_process_info(&self) -> Result<Vec<ProcessInfo>> {
+        let conn = self.connection().await?;
+        let result = conn
+            .send(None, "SystemInfo.getProcessInfo", json!({}))
+            .await?;
+        let mut processes = ProcessInfo::list_from(&result);
+
+        let sessions: Vec<(TabId, CdpSession)> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect();
+
+        // Traced side by side, so a stuck tab costs one timeout rather than one each
+        let renderers = join_all(sessions.iter().map(|(tab_id, session)| async move {
+            let pid = match renderer_pid(session).await {
+                Ok(pid) => pid?,
+                Err(e) => {
+                    warn!("Failed to find the renderer for tab {}: {}", tab_id, e);
+                    return None;
+                }
+            };
+            match tab_metrics(tab_id, session).await {
+                Ok(metrics) => Some((pid, metrics)),
+                Err(e) => {
+                    warn!("Failed to read metrics for tab {}: {}", tab_id, e);
+                    None
+                }
+            }
+        }))
+        .await;
+
+        for (pid, metrics) in renderers.into_iter().flatten() {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == pid) {
+                process.add_tab(&metrics);
+            }
+        }
+
+        Ok(processes)
+    }
+
+    /// Crashes the renderer hosting a tab and leaves the tab discarded so it
+    /// reloads on next switch.
+    ///
+    /// Chrome may share one renderer between same-site tabs, which go down with it.
+    pub async fn kill_tab_process(&self, tab_id: &str) -> Result<()> {
+        // A tab without a target has no renderer to kill
+        let session = self.sessions.read().await.get(tab_id).cloned();
+        let session = session.ok_or_else(|| {
+            BrowserError::NotFound(format!("Tab {} has no renderer process", tab_id))
+        })?;
+
+        // The renderer dies before it can reply, so don't wait on a response
+        let _ = tokio::time::timeout(
+            Duration::from_secs(2),
+            session.send("Page.crash", json!({})),
+        )
+        .await;
+
+        self.close_target(tab_id).await;
+        self.set_lifecycle_state(tab_id, LifecycleState::Discarded)
+            .await;
+
+        warn!("Killed renderer process for tab {}", tab_id);
+        Ok(())
+    }
+
+    /// Highlights every match of `query` across the tab's frames and selects the first.
+    pub async fn find_in_page(
+        &self,
+        tab_id: &str,
+        query: &str,
+        case_sensitive: bool,
+    ) -> Result<FindResult> {
+        self.clear_find(tab_id).await?;
+        let session = self.session(tab_id).await?;
+
+        let tree = session.send("Page.getFrameTree", json!({})).await?;
+        let mut frame_ids = Vec::new();
+        collect_frame_ids(&tree["frameTree"], &mut frame_ids);
+
+        let expression = format!(
+            "{}; window.__aetherFind.search({}, {})",
+            FIND_SCRIPT,
+            serde_json::to_string(query)?,
+            case_sensitive
+        );
+
+        let mut frames = Vec::with_capacity(frame_ids.len());
+        for frame_id in frame_ids {
+            // A frame that detached or is mid-navigation shouldn't stop the rest being searched
+            match search_frame(&session, &frame_id, &expression).await {
+                Ok(Some(frame)) => frames.push(frame),
+                Ok(None) => {}
+                Err(e) => warn!("Skipping frame {} in find: {}", frame_id, e),
+            }
+        }
+
+        let find = FindSession {
+            query: query.to_string(),
+            case_sensitive,
+            frames,
+            current: None,
+        };
+        self.find_sessions
+            .write()
+            .await
+            .insert(tab_id.to_string(), find);
+
+        debug!("Find \"{}\" in tab {}", query, tab_id);
+        self.find_step(tab_id, true).await
+    }
+
+    /// Moves to the next (or previous) match, wrapping around the page.
+    pub async fn find_step(&self, tab_id: &str, forward: bool) -> Result<FindResult> {
+        let mut find = self
+            .find_sessions
+            .read()
+            .await
+            .get(tab_id)
+            .cloned()
+            .ok_or_else(|| BrowserError::NotFound(format!("No active find in tab {}", tab_id)))?;
+
+        let Some(next) = find.step(forward) else {
+            return Ok(find.result());
+        };
+        let session = self.session(tab_id).await?;
+
+        let previous_context = find.current.and_then(|i| find.locate(i)).map(|(c, _)| c);
+        let (context_id, local_index) = find
+            .locate(next)
+            .ok_or_else(|| BrowserError::NotFound(format!("Match {} not found", next)))?;
+
+        if let Some(previous_context) = previous_context.filter(|c| *c != context_id) {
+            session
+                .send(
+                    "Runtime.evaluate",
+                    json!({
+                        "expression": "window.__aetherFind?.activate(-1)",
+                        "contextId": previous_context,
+                    }),
+                )
+                .await?;
+        }
+
+        session
+            .send(
+                "Runtime.evaluate",
+                json!({
+                    "expression": format!("window.__aetherFind.activate({})", local_index),
+                    "contextId": context_id,
+                }),
+            )
+            .await?;
+
+        find.current = Some(next);
+        let result = find.result();
+        self.find_sessions
+            .write()
+            .await
+            .insert(tab_id.to_string(), find);
+
+        Ok(result)
+    }
+
+    /// Runs `expression` in a tab and returns its JSON value or the exception it threw.
+    ///
+    /// Promises are awaited. Isolated calls share one world per document, which the
+    /// page's own scripts can't see.
+    pub async fn evaluate(
+        &self,
+        tab_id: &str,
+        expression: &str,
+        world: ScriptWorld,
+    ) -> Result<EvaluationResult> {
+        let session = self.session(tab_id).await?;
+
+        let mut params = json!({
+            "expression": expression,
+            "returnByValue": true,
+            "awaitPromise": true,
+        });
+        if let Some(context_id) = self.script_context(tab_id, &session, world).await? {
+            params["contextId"] = json!(context_id);
+        }
+
+        let result = session.send("Runtime.evaluate", params).await?;
+        Ok(EvaluationResult::from_cdp(&result))
+    }
+
+    /// Calls `function_declaration` (e.g. `"(a, b) => a + b"`) with JSON arguments.
+    ///
+    /// Passing arguments this way avoids splicing untrusted values into script source.
+    pub async fn call_function(
+        &self,
+        tab_id: &str,
+        function_declaration: &str,
+        args: &[Value],
+        world: ScriptWorld,
+    ) -> Result<EvaluationResult> {
+        let session = self.session(tab_id).await?;
+
+        let mut params = json!({
+            "functionDeclaration": function_declaration,
+            "arguments": args.iter().map(|v| json!({ "value": v })).collect::<Vec<_>>(),
+            "returnByValue": true,
+            "awaitPromise": true,
+        });
+
+        // callFunctionOn needs a target: a context id for isolated worlds, or the
+        // main world's global object otherwise
+        let global_object = match self.script_context(tab_id, &session, world).await? {
+            Some(context_id) => {
+                params["executionContextId"] = json!(context_id);
+                None
+            }
+            None => {
+                let global = session
+                    .send("Runtime.evaluate", json!({ "expression": "globalThis" }))
+                    .await?;
+                let object_id = global["result"]["objectId"].clone();
+                params["objectId"] = object_id.clone();
+                Some(object_id)
+            }
+        };
+
+        let result = session.send("Runtime.callFunctionOn", params).await;
+
+        if let Some(object_id) = global_object {
+            let _ = session
+                .send("Runtime.releaseObject", json!({ "objectId": object_id }))
+                .await;
+        }
+
+        Ok(EvaluationResult::from_cdp(&result?))
+    }
+
+    /// The context of the isolated world in the tab's main frame, or `None` for the
+    /// page's world.
+    async fn script_context(
+        &self,
+        tab_id: &str,
+        session: &CdpSession,
+        world: ScriptWorld,
+    ) -> Result<Option<i64>> {
+        if world == ScriptWorld::Main {
+            return Ok(None);
+        }
+        isolated_world(&self.isolated_worlds, tab_id, session)
+            .await
+            .map(Some)
+    }
+
+    /// Sets the proxy for the default profile. Takes effect the next time Chrome launches.
+    pub async fn set_proxy_config(&self, proxy: ProxyConfig) {
+        *self.proxy.write().await = proxy;
+    }
+
+    /// Sets the login for the default profile's proxy. Tabs attached from now on answer
+    /// its auth challenges with it.
+    pub async fn set_proxy_credentials(&self, credentials: Option<ProxyCredentials>) {
+        *self.proxy_credentials.write().await = credentials;
+    }
+
+    /// Creates an isolated private browser context, optionally behind its own proxy.
+    pub async fn create_browser_context(
+        &self,
+        proxy: Option<ProxyConfig>,
+        credentials: Option<ProxyCredentials>,
+    ) -> Result<String> {
+        let conn = self.connection().await?;
+        let proxy = proxy.unwrap_or_default();
+
+        let result = conn
+            .send(
+                None,
+                "Target.createBrowserContext",
+                proxy.browser_context_params(),
+            )
+            .await?;
+        let context_id = result["browserContextId"]
+            .as_str()
+            .ok_or_else(|| BrowserError::Cdp("createBrowserContext returned no id".to_string()))?
+            .to_string();
+
+        let credentials = credentials.filter(|_| proxy.server().is_some());
+        self.browser_contexts
+            .write()
+            .await
+            .insert(context_id.clone(), credentials);
+
+        info!("Created browser context {}", context_id);
+        Ok(context_id)
+    }
+
+    /// Closes every tab in a private context and throws away its storage.
+    pub async fn close_browser_context(&self, context_id: &str) -> Result<()> {
+        if self
+            .browser_contexts
+            .write()
+            .await
+            .remove(context_id)
+            .is_none()
+        {
+            return Err(BrowserError::NotFound(format!(
+                "Browser context {} not found",
+                context_id
+            )));
+        }
+
+        let tab_ids: Vec<TabId> = self
+            .tabs
+            .read()
+            .await
+            .values()
+            .filter(|t| t.browser_context_id.as_deref() == Some(context_id))
+            .map(|t| t.id.clone())
+            .collect();
+        for tab_id in tab_ids {
+            self.close_tab(&tab_id).await?;
+        }
+
+        let conn = self.connection().await?;
+        conn.send(
+            None,
+            "Target.disposeBrowserContext",
+            json!({ "browserContextId": context_id }),
+        )
+        .await?;
+
+        info!("Closed browser context {}", context_id);
+        Ok(())
+    }
+
+    /// Replaces the request rules. Open tabs pick up the change immediately.
+    pub async fn set_request_rules(&self, rules: Vec<RequestRule>) -> Result<()> {
+        *self.request_rules.write().await = rules;
+
+        let sessions: Vec<(TabId, CdpSession)> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect();
+        for (tab_id, session) in sessions {
+            let browser_context_id = self
+                .tabs
+                .read()
+                .await
+                .get(&tab_id)
+                .and_then(|t| t.browser_context_id.clone());
+            self.update_interception(&tab_id, &session, browser_context_id.as_deref())
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn set_popup_rules(&self, rules: Vec<PopupRule>) {
+        *self.popup_policies.write().await =
+            rules.into_iter().map(|r| (r.host, r.policy)).collect();
+    }
+
+    /// Applies the opener site's popup policy to a page target Chrome just created.
+    pub async fn handle_popup(&self, target_info: &Value) -> Result<()> {
+        let target_id = target_info["targetId"].as_str().unwrap_or_default();
+        let opener_target = target_info["openerId"].as_str().unwrap_or_default();
+
+        let opener_id = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .find(|(_, session)| session.target_id == opener_target)
+            .map(|(id, _)| id.clone());
+        let Some(opener_id) = opener_id else {
+            // Opened by a target we don't manage
+            return Ok(());
+        };
+        let Some(opener) = self.tabs.read().await.get(&opener_id).cloned() else {
+            return Ok(());
+        };
+
+        let policy = match popup_host(&opener.url) {
+            Some(host) => self
+                .popup_policies
+                .read()
+                .await
+                .get(&host)
+                .copied()
+                .unwrap_or_default(),
+            None => PopupPolicy::default(),
+        };
+        let conn = self.connection().await?;
+
+        match policy {
+            PopupPolicy::Block => {
+                conn.send(None, "Target.closeTarget", json!({ "targetId": target_id }))
+                    .await?;
+                info!("Blocked popup from tab {}", opener_id);
+            }
+            PopupPolicy::Redirect => {
+                let initial_url = target_info["url"].as_str().unwrap_or_default();
+                let url = wait_for_target_url(&conn, target_id, initial_url).await;
+                conn.send(None, "Target.closeTarget", json!({ "targetId": target_id }))
+                    .await?;
+                if let Some(url) = url {
+                    self.navigate_tab(&opener_id, &url).await?;
+                    info!("Redirected popup from tab {} into the opener", opener_id);
+                }
+            }
+            PopupPolicy::Allow => {
+                self.adopt_popup(&conn, target_info, &opener).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turns a popup target into a tab placed right after its opener.
+    async fn adopt_popup(
+        &self,
+        conn: &Arc<CdpConnection>,
+        target_info: &Value,
+        opener: &Tab,
+    ) -> Result<()> {
+        let target_id = target_info["targetId"].as_str().unwrap_or_default();
+        let session = CdpSession::attach(conn.clone(), target_id).await?;
+
+        let tab_id = uuid::Uuid::new_v4().to_string();
+        let title = target_info["title"]
+            .as_str()
+            .filter(|t| !t.is_empty())
+            .unwrap_or("New Tab");
+        let tab = Tab {
+            id: tab_id.clone(),
+            url: target_info["url"]
+                .as_str()
+                .unwrap_or("about:blank")
+                .to_string(),
+            title: title.to_string(),
+            favicon: None,
+            window_id: opener.window_id.clone(),
+            position: opener.position + 1,
+            is_active: false,
+            lifecycle_state: LifecycleState::Active,
+            opener_id: Some(opener.id.clone()),
+            browser_context_id: opener.browser_context_id.clone(),
+            // Placed right after its opener, so it joins the opener's group
+            group_id: opener.group_id.clone(),
+            pinned: false,
+            security: TabSecurity::default(),
+        };
+
+        {
+            let mut tabs = self.tabs.write().await;
+            for t in tabs
+                .values_mut()
+                .filter(|t| t.window_id == opener.window_id)
+            {
+                if t.position >= tab.position {
+                    t.position += 1;
+                }
+            }
+            tabs.insert(tab_id.clone(), tab);
+            // Popups from a pinned tab land after the pinned ones
+            normalize_positions(&mut tabs);
+        }
+        self.sessions
+            .write()
+            .await
+            .insert(tab_id.clone(), session.clone());
+        self.update_interception(&tab_id, &session, opener.browser_context_id.as_deref())
+            .await?;
+        self.start_security_monitoring(&tab_id, &session).await?;
+        self.start_navigation_watch(&tab_id, &session).await?;
+        if let Some(tab) = self.tabs.read().await.get(&tab_id).cloned() {
+            emit(BrowserEvent::TabCreated { tab });
+        }
+        self.switch_tab(&tab_id).await?;
+
+        info!("Opened popup from tab {} as tab {}", opener.id, tab_id);
+        Ok(())
+    }
+
+    /// Emulates network conditions in a tab until changed or reset with `NetworkEmulation::None`.
+    pub async fn set_network_emulation(
+        &self,
+        tab_id: &str,
+        emulation: NetworkEmulation,
+    ) -> Result<()> {
+        let session = self.session(tab_id).await?;
+        session.send("Network.enable", json!({})).await?;
+        session
+            .send(
+                "Network.emulateNetworkConditions",
+                emulation.to_cdp_params(),
+            )
+            .await?;
+        session
+            .send(
+                "Emulation.setCPUThrottlingRate",
+                emulation.to_cpu_throttling_params(),
+            )
+            .await?;
+
+        let mut emulations = self.network_emulation.write().await;
+        if emulation == NetworkEmulation::None {
+            emulations.remove(tab_id);
+        } else {
+            emulations.insert(tab_id.to_string(), emulation);
+        }
+
+        info!("Network emulation for tab {}: {:?}", tab_id, emulation);
+        Ok(())
+    }
+
+    pub async fn get_network_emulation(&self, tab_id: &str) -> NetworkEmulation {
+        self.network_emulation
+            .read()
+            .await
+            .get(tab_id)
+            .copied()
+            .unwrap_or(NetworkEmulation::None)
+    }
+
+    /// Flips a tab between offline and normal networking. Returns whether it is now offline.
+    pub async fn toggle_offline(&self, tab_id: &str) -> Result<bool> {
+        let offline = !self.get_network_emulation(tab_id).await.is_offline();
+        let emulation = if offline {
+            NetworkEmulation::Offline
+        } else {
+            NetworkEmulation::None
+        };
+        self.set_network_emulation(tab_id, emulation).await?;
+        Ok(offline)
+    }
+
+    pub async fn set_zoom_levels(&self, levels: Vec<ZoomLevel>) {
+        *self.zoom_levels.write().await = levels.into_iter().map(|z| (z.origin, z.level)).collect();
+    }
+
+    /// Zooms a tab one step and remembers the new level for every tab on its origin.
+    pub async fn zoom_tab(&self, tab_id: &str, action: ZoomAction) -> Result<ZoomLevel> {
+        let url = self
+            .tabs
+            .read()
+            .await
+            .get(tab_id)
+            .map(|t| t.url.clone())
+            .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
+        let origin = zoom_origin(&url).ok_or_else(|| {
+            BrowserError::Configuration(format!("Pages at {} can't be zoomed", url))
+        })?;
+
+        let level = next_zoom_level(self.zoom_for(&url).await, action);
+        if level == DEFAULT_ZOOM {
+            self.zoom_levels.write().await.remove(&origin);
+        } else {
+            self.zoom_levels.write().await.insert(origin.clone(), level);
+        }
+
+        // Other open tabs on the same site follow along
+        let tab_ids: Vec<TabId> = self
+            .tabs
+            .read()
+            .await
+            .values()
+            .filter(|t| zoom_origin(&t.url).as_deref() == Some(origin.as_str()))
+            .map(|t| t.id.clone())
+            .collect();
+        for id in tab_ids {
+            let session = self.sessions.read().await.get(&id).cloned();
+            if let Some(session) = session {
+                apply_zoom(&self.isolated_worlds, &id, &session, level).await?;
+            }
+        }
+
+        info!("Zoom for {} set to {}", origin, level);
+        Ok(ZoomLevel { origin, level })
+    }
+
+    async fn zoom_for(&self, url: &str) -> f64 {
+        zoom_for_url(&*self.zoom_levels.read().await, url)
+    }
+
+    /// Lets the tab through the certificate error it is stopped on and reloads it.
+    ///
+    /// The host stays allowed until Aether restarts. Returns the error that was
+    /// bypassed so the caller can record the decision.
+    pub async fn proceed_certificate_error(&self, tab_id: &str) -> Result<CertificateError> {
+        let error = {
+            let mut tabs = self.tabs.write().await;
+            let t = tabs
+                .get_mut(tab_id)
+                .ok_or_else(|| BrowserError::NotFound(format!("Tab {} not found", tab_id)))?;
+            let error = t.security.certificate_error.take().ok_or_else(|| {
+                BrowserError::NotFound(format!("Tab {} has no certificate error", tab_id))
+            })?;
+            t.url = error.url.clone();
+            emit(BrowserEvent::TabUpdated { tab: t.clone() });
+            error
+        };
+
+        self.allowed_cert_hosts
+            .write()
+            .await
+            .insert(error.host.clone());
+
+        let session = self.session(tab_id).await?;
+        session
+            .send("Page.navigate", json!({ "url": error.url }))
+            .await?;
+
+        warn!("Proceeding to {} despite {}", error.host, error.error_type);
+        Ok(error)
+    }
+
+    /// Lists origins that have cookies or stored data, largest first.
+    ///
+    /// Origins come from the profile's cookies plus the sites open in tabs, since
+    /// Chrome has no call that enumerates every origin with storage.
+    pub async fn get_site_data(&self) -> Result<Vec<SiteData>> {
+        let conn = self.connection().await?;
+        let cookies = conn.send(None, "Storage.getCookies", json!({})).await?;
+
+        let mut origins = cookie_origins(&cookies);
+        for tab in self.tabs.read().await.values() {
+            if let Some(origin) = site_origin(&tab.url) {
+                origins.entry(origin).or_insert(0);
+            }
+        }
+
+        let mut sites = Vec::with_capacity(origins.len());
+        for (origin, cookie_count) in origins {
+            let usage = conn
+                .send(
+                    None,
+                    "Storage.getUsageAndQuota",
+                    json!({ "origin": origin }),
+                )
+                .await;
+            let site = match usage {
+                Ok(usage) => SiteData::from_usage(origin, cookie_count, &usage),
+                Err(e) => {
+                    warn!("Failed to read storage usage for {}: {}", origin, e);
+                    SiteData::without_usage(origin, cookie_count)
+                }
+            };
+            if !site.is_empty() {
+                sites.push(site);
+            }
+        }
+
+        sites.sort_by_key(|s| std::cmp::Reverse(s.usage));
+        Ok(sites)
+    }
+
+    /// Clears the chosen kinds of data for a site, or all of them if `types` is empty.
+    pub async fn clear_site_data(&self, site: &str, types: &[SiteDataType]) -> Result<String> {
+        let origin = site_origin(site)
+            .ok_or_else(|| BrowserError::Configuration(format!("Invalid site: {}", site)))?;
+        let types = if types.is_empty() {
+            &SiteDataType::ALL[..]
+        } else {
+            types
+        };
+
+        let conn = self.connection().await?;
+        conn.send(
+            None,
+            "Storage.clearDataForOrigin",
+            json!({ "origin": origin, "storageTypes": storage_types_param(types) }),
+        )
+        .await?;
+
+        info!("Cleared {:?} for {}", types, origin);
+        Ok(origin)
+    }
+
+    /// Starts streaming a tab's rendered frames to the frontend as `screencast-frame` events.
+    pub async fn start_screencast(
+        &self,
+        app_handle: tauri::AppHandle,
+        tab_id: &str,
+        settings: ScreencastSettings,
+    ) -> Result<()> {
+        self.stop_screencast(tab_id).await?;
+        let session = self.session(tab_id).await?;
+
+        // Subscribe before starting so the first frame can't be missed
+        let events = session.subscribe_frames();
+        session.send("Page.enable", json!({})).await?;
+        session
+            .send("Page.startScreencast", settings.to_cdp_params())
+            .await?;
+
+        let pump = tokio::spawn(pump_frames(
+            app_handle,
+            session,
+            tab_id.to_string(),
+            settings,
+            events,
+        ));
+        self.screencasts
+            .write()
+            .await
+            .insert(tab_id.to_string(), pump);
+
+        info!("Started screencast for tab {}", tab_id);
+        Ok(())
+    }
+
+    pub async fn stop_screencast(&self, tab_id: &str) -> Result<()> {
+        let Some(pump) = self.screencasts.write().await.remove(tab_id) else {
+            return Ok(());
+        };
+        pump.abort();
+
+        let session = self.session(tab_id).await?;
+        session.send("Page.stopScreencast", json!({})).await?;
+
+        info!("Stopped screencast for tab {}", tab_id);
+        Ok(())
+    }
+
+    pub async fn dispatch_mouse_event(&self, tab_id: &str, input: &MouseInput) -> Result<()> {
+        let session = self.session(tab_id).await?;
+        session
+            .send("Input.dispatchMouseEvent", input.to_cdp_params())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn dispatch_key_event(&self, tab_id: &str, input: &KeyInput) -> Result<()> {
+        let session = self.session(tab_id).await?;
+        session
+            .send("Input.dispatchKeyEvent", input.to_cdp_params())
+            .await?;
+        Ok(())
+    }
+
+    /// Screenshots the viewport with a numbered box over every visible interactive
+    /// element, and remembers which element each number refers to.
+    ///
+    /// Only the top frame's elements are marked.
+    pub async fn capture_marked_screenshot(&self, tab_id: &str) -> Result<MarkedScreenshot> {
+        let session = self.session(tab_id).await?;
+
+        let metrics = session.send("Page.getLayoutMetrics", json!({})).await?;
+        let view_width = metrics["cssLayoutViewport"]["clientWidth"]
+            .as_f64()
+            .unwrap_or_default();
+        let view_height = metrics["cssLayoutViewport"]["clientHeight"]
+            .as_f64()
+            .unwrap_or_default();
+
+        let document = session
+            .send("DOM.getDocument", json!({ "depth": 0 }))
+            .await?;
+        let root_id = document["root"]["nodeId"].as_i64().unwrap_or_default();
+        let nodes = session
+            .send(
+                "DOM.querySelectorAll",
+                json!({ "nodeId": root_id, "selector": MARKABLE_SELECTOR }),
+            )
+            .await?;
+        let node_ids: Vec<i64> = nodes["nodeIds"]
+            .as_array()
+            .map(|ids| ids.iter().filter_map(Value::as_i64).collect())
+            .unwrap_or_default();
+
+        let mut marks: Vec<Mark> = Vec::new();
+        for node_id in node_ids {
+            if marks.len() >= MAX_MARKS {
+                break;
+            }
+
+            // Elements that aren't rendered have no box model
+            let Ok(model) = session
+                .send("DOM.getBoxModel", json!({ "nodeId": node_id }))
+                .await
+            else {
+                continue;
+            };
+            let Some((x, y, width, height)) = quad_bounds(&model["model"]["border"]) else {
+                continue;
+            };
+            let on_screen =
+                x + width > 0.0 && y + height > 0.0 && x < view_width && y < view_height;
+            if width < 1.0 || height < 1.0 || !on_screen {
+                continue;
+            }
+
+            let described = session
+                .send("DOM.describeNode", json!({ "nodeId": node_id }))
+                .await?;
+            let node = &described["node"];
+            marks.push(Mark {
+                number: marks.len() as u32 + 1,
+                backend_node_id: node["backendNodeId"].as_i64().unwrap_or_default(),
+                tag: node["localName"].as_str().unwrap_or_default().to_string(),
+                label: element_label(&node["attributes"]),
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+
+        let mut params = json!({ "expression": overlay_expression(&marks) });
+        if let Some(context_id) = self
+            .script_context(tab_id, &session, ScriptWorld::Isolated)
+            .await?
+        {
+            params["contextId"] = json!(context_id);
+        }
+        session.send("Runtime.evaluate", params.clone()).await?;
+        let screenshot = session
+            .send("Page.captureScreenshot", json!({ "format": "png" }))
+            .await;
+
+        // Take the overlay down even if the capture failed
+        params["expression"] = json!(REMOVE_OVERLAY);
+        let _ = session.send("Runtime.evaluate", params).await;
+        let image = screenshot?["data"].as_str().unwrap_or_default().to_string();
+
+        self.marks
+            .write()
+            .await
+            .insert(tab_id.to_string(), marks.clone());
+
+        debug!("Marked {} elements in tab {}", marks.len(), tab_id);
+        Ok(MarkedScreenshot { image, marks })
+    }
+
+    /// Looks up a mark from the tab's latest set-of-marks screenshot.
+    pub async fn resolve_mark(&self, tab_id: &str, number: u32) -> Result<Mark> {
+        self.marks
+            .read()
+            .await
+            .get(tab_id)
+            .and_then(|marks| marks.iter().find(|m| m.number == number))
+            .cloned()
+            .ok_or_else(|| {
+                BrowserError::NotFound(format!("Mark {} not found in tab {}", number, tab_id))
+            })
+    }
+
+    /// Clicks the element behind a mark, wherever it has moved to since the screenshot.
+    pub async fn click_mark(&self, tab_id: &str, number: u32) -> Result<Mark> {
+        let mut mark = self.resolve_mark(tab_id, number).await?;
+        let session = self.session(tab_id).await?;
+
+        let node = json!({ "backendNodeId": mark.backend_node_id });
+        session
+            .send("DOM.scrollIntoViewIfNeeded", node.clone())
+            .await?;
+        let model = session.send("DOM.getBoxModel", node).await?;
+        let (x, y, width, height) = quad_bounds(&model["model"]["border"])
+            .ok_or_else(|| BrowserError::Cdp(format!("Mark {} has no box", number)))?;
+        (mark.x, mark.y, mark.width, mark.height) = (x, y, width, height);
+
+        let (x, y) = mark.center();
+        for kind in [MouseEventKind::MousePressed, MouseEventKind::MouseReleased] {
+            let input = MouseInput {
+                kind,
+                x,
+                y,
+                button: Some("left".to_string()),
+                click_count: 1,
+                delta_x: 0.0,
+                delta_y: 0.0,
+                modifiers: 0,
+            };
+            session
+                .send("Input.dispatchMouseEvent", input.to_cdp_params())
+                .await?;
+        }
+
+        info!("Clicked mark {} ({}) in tab {}", number, mark.tag, tab_id);
+        Ok(mark)
+    }
+
+    /// Removes all find highlights from a tab.
+    pub async fn clear_find(&self, tab_id: &str) -> Result<()> {
+        let Some(find) = self.find_sessions.write().await.remove(tab_id) else {
+            return Ok(());
+        };
+        let session = self.session(tab_id).await?;
+
+        for frame in find.frames {
+            // Frames may have navigated away since the search; nothing left to clear there
+            let _ = session
+                .send(
+                    "Runtime.evaluate",
+                    json!({
+                        "expression": "window.__aetherFind?.clear()",
+                        "contextId": frame.context_id,
+                    }),
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the files on an `<input type=file>` matched by `selector`.
+    ///
+    /// Callers are expected to have run the paths through `validate_upload_paths`.
+    pub async fn set_file_input_files(
+        &self,
+        tab_id: &str,
+        selector: &str,
+        files: &[String],
+    ) -> Result<()> {
+        let session = self.session(tab_id).await?;
+
+        let document = session
+            .send("DOM.getDocument", json!({ "depth": 0 }))
+            .await?;
+        let root_id = document["root"]["nodeId"].as_i64().unwrap_or_default();
+
+        let node = session
+            .send(
+                "DOM.querySelector",
+                json!({ "nodeId": root_id, "selector": selector }),
+            )
+            .await?;
+        let node_id = node["nodeId"].as_i64().unwrap_or_default();
+        if node_id == 0 {
+            return Err(BrowserError::NotFound(format!(
+                "No element matches selector {}",
+                selector
+            )));
+        }
+
+        session
+            .send(
+                "DOM.setFileInputFiles",
+                json!({ "files": files, "nodeId": node_id }),
+            )
+            .await?;
+
+        info!(
+            "Set {} file(s) on {} in tab {}",
+            files.len(),
+            selector,
+            tab_id
+        );
+        Ok(())
+    }
+
+    /// Intercepts file chooser dialogs in a tab and answers them with `files`.
+    ///
+    /// Passing `None` stops intercepting and lets Chrome show its own dialog again.
+    pub async fn intercept_file_chooser(
+        &self,
+        tab_id: &str,
+        files: Option<Vec<String>>,
+    ) -> Result<()> {
+        let session = self.session(tab_id).await?;
+
+        let Some(files) = files else {
+            self.file_chooser_files.write().await.remove(tab_id);
+            if let Some(pump) = self.file_chooser_pumps.write().await.remove(tab_id) {
+                pump.abort();
+            }
+            session
+                .send(
+                    "Page.setInterceptFileChooserDialog",
+                    json!({ "enabled": false }),
+                )
+                .await?;
+            info!("Stopped intercepting file choosers in tab {}", tab_id);
+            return Ok(());
+        };
+
+        self.file_chooser_files
+            .write()
+            .await
+            .insert(tab_id.to_string(), files);
+
+        session.send("Page.enable", json!({})).await?;
+        session
+            .send(
+                "Page.setInterceptFileChooserDialog",
+                json!({ "enabled": true }),
+            )
+            .await?;
+
+        // Re-enabling starts no second listener, as disabling stopped the first
+        let mut pumps = self.file_chooser_pumps.write().await;
+        if !pumps.get(tab_id).is_some_and(|pump| !pump.is_finished()) {
+            let mut events = session.subscribe();
+            let file_chooser_files = self.file_chooser_files.clone();
+            let tab = tab_id.to_string();
+
+            let pump = tokio::spawn(async move {
+                while let Some(event) = recv_event(&mut events).await {
+                    if !event.is_for(&session) || event.method != "Page.fileChooserOpened" {
+                        continue;
+                    }
+
+                    let Some(files) = file_chooser_files.read().await.get(&tab).cloned() else {
+                        break;
+                    };
+
+                    let result = session
+                        .send(
+                            "DOM.setFileInputFiles",
+                            json!({
+                                "files": files,
+                                "backendNodeId": event.params["backendNodeId"],
+                            }),
+                        )
+                        .await;
+
+                    match result {
+                        Ok(_) => info!("Answered file chooser in tab {}", tab),
+                        Err(e) => error!("Failed to answer file chooser in tab {}: {}", tab, e),
+                    }
+                }
+            });
+            pumps.insert(tab_id.to_string(), pump);
+        }
+        drop(pumps);
+
+        info!("Intercepting file choosers in tab {}", tab_id);
+        Ok(())
+    }
+}
+
+const ISOLATED_WORLD_NAME: &str = "aether";
+
+/// The execution context of Aether's isolated world in a tab's main frame.
+///
+/// A world lives as long as its document, so one is made per document and reused
+/// rather than adding another to the page on every call.
+#[derive(Debug, Clone)]
+struct IsolatedWorld {
+    loader_id: String,
+    context_id: i64,
+}
+
+type IsolatedWorlds = Arc<RwLock<HashMap<TabId, IsolatedWorld>>>;
+
+async fn isolated_world(
+    worlds: &IsolatedWorlds,
+    tab_id: &str,
+    session: &CdpSession,
+) -> Result<i64> {
+    let tree = session.send("Page.getFrameTree", json!({})).await?;
+    let frame = &tree["frameTree"]["frame"];
+    let loader_id = frame["loaderId"].as_str().unwrap_or_default();
+    if let Some(world) = worlds.read().await.get(tab_id) {
+        if world.loader_id == loader_id {
+            return Ok(world.context_id);
+        }
+    }
+
+    let created = session
+        .send(
+            "Page.createIsolatedWorld",
+            json!({ "frameId": frame["id"], "worldName": ISOLATED_WORLD_NAME }),
+        )
+        .await?;
+    let context_id = created["executionContextId"]
+        .as_i64()
+        .ok_or_else(|| BrowserError::Cdp("createIsolatedWorld returned no context".to_string()))?;
+    worlds.write().await.insert(
+        tab_id.to_string(),
+        IsolatedWorld {
+            loader_id: loader_id.to_string(),
+            context_id,
+        },
+    );
+    Ok(context_id)
+}
+
+/// Zooms the page with CSS `zoom` on its root element, which reflows the layout the
+/// way Chrome's own zoom does, below 100% too. It runs in an isolated world so the
+/// page's scripts can't shadow what it uses.
+async fn apply_zoom(
+    worlds: &IsolatedWorlds,
+    tab_id: &str,
+    session: &CdpSession,
+    level: f64,
+) -> Result<()> {
+    let context_id = isolated_world(worlds, tab_id, session).await?;
+
+    let zoom = if level == DEFAULT_ZOOM {
+        String::new()
+    } else {
+        level.to_string()
+    };
+    session
+        .send(
+            "Runtime.evaluate",
+            json!({
+                "expression": format!(
+                    "document.documentElement.style.zoom = {}",
+                    serde_json::to_string(&zoom)?
+                ),
+                "contextId": context_id,
+            }),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Renumbers each window's tab positions to `0..n` keeping their order, pinned tabs
+/// first, and returns the ids window by window in that order.
+///
+/// Ties are broken by id so two tabs that ended up on the same position always settle the same way.
+fn normalize_positions(tabs: &mut HashMap<TabId, Tab>) -> Vec<TabId> {
+    let mut order: Vec<(WindowId, bool, i32, TabId)> = tabs
+        .values()
+        .map(|t| (t.window_id.clone(), !t.pinned, t.position, t.id.clone()))
+        .collect();
+    order.sort();
+
+    let mut position = 0;
+    let mut window = None;
+    order
+        .into_iter()
+        .map(|(window_id, _, _, id)| {
+            if window.as_ref() != Some(&window_id) {
+                position = 0;
+                window = Some(window_id);
+            }
+            if let Some(t) = tabs.get_mut(&id) {
+                t.position = position;
+            }
+            position += 1;
+            id
+        })
+        .collect()
+}
+
+/// Normalizes positions and returns one window's tabs in order.
+fn window_order(tabs: &mut HashMap<TabId, Tab>, window_id: &str) -> Vec<TabId> {
+    let mut order = normalize_positions(tabs);
+    order.retain(|id| tabs[id].window_id == window_id);
+    order
+}
+
+/// Moves `ids` as one block to `index` among the other tabs of the first one's window
+/// and renumbers that window's tabs. Returns the window's new order.
+///
+/// The index is kept on the block's own side of the pinned tabs. With `keep_groups_whole`,
+/// an index inside a group is pushed past its end.
+fn place_tabs(
+    tabs: &mut HashMap<TabId, Tab>,
+    ids: &[TabId],
+    index: usize,
+    keep_groups_whole: bool,
+) -> Vec<TabId> {
+    let window_id = tabs[&ids[0]].window_id.clone();
+    let mut order = window_order(tabs, &window_id);
+    order.retain(|id| !ids.contains(id));
+
+    let pinned = order.iter().filter(|id| tabs[*id].pinned).count();
+    let mut index = if tabs[&ids[0]].pinned {
+        index.min(pinned)
+    } else {
+        index.clamp(pinned, order.len())
+    };
+    if keep_groups_whole {
+        index = index_outside_groups(tabs, &order, index);
+    }
+    order.splice(index..index, ids.iter().cloned());
+
+    for (position, id) in order.iter().enumerate() {
+        if let Some(t) = tabs.get_mut(id) {
+            t.position = position as i32;
+        }
+    }
+    order
+}
+
+/// Runs the find script in an isolated world of one frame.
+async fn search_frame(
+    session: &CdpSession,
+    frame_id: &str,
+    expression: &str,
+) -> Result<Option<FrameMatches>> {
+    let world = session
+        .send(
+            "Page.createIsolatedWorld",
+            json!({ "frameId": frame_id, "worldName": "aether-find" }),
+        )
+        .await?;
+    let Some(context_id) = world["executionContextId"].as_i64() else {
+        return Ok(None);
+    };
+
+    let result = session
+        .send(
+            "Runtime.evaluate",
+            json!({
+                "expression": expression,
+                "contextId": context_id,
+                "returnByValue": true,
+            }),
+        )
+        .await?;
+    let count = result["result"]["value"].as_u64().unwrap_or_default() as usize;
+    Ok(Some(FrameMatches { context_id, count }))
+}
+
+fn collect_frame_ids(tree: &Value, frame_ids: &mut Vec<String>) {
+    if let Some(id) = tree["frame"]["id"].as_str() {
+        frame_ids.push(id.to_string());
+    }
+    if let Some(children) = tree["childFrames"].as_array() {
+        for child in children {
+            collect_frame_ids(child, frame_ids);
+        }
+    }
+}
+
+/// Checks that every file exists and lives under one of the user-approved paths.
+///
+/// Returns the canonicalized paths so symlinks can't be used to escape an approved directory.
+pub fn validate_upload_paths(files: &[String], approved: &[String]) -> Result<Vec<String>> {
+    let approved: Vec<PathBuf> = approved
+        .iter()
+        .filter_map(|p| Path::new(p).canonicalize().ok())
+        .collect();
+
+    files
+        .iter()
+        .map(|file| {
+            let path = Path::new(file).canonicalize().map_err(|e| {
+                BrowserError::NotFound(format!("Cannot read upload file {}: {}", file, e))
+            })?;
+
+            if !path.is_file() {
+                return Err(BrowserError::NotFound(format!("{} is not a file", file)));
+            }
+
+            if !approved.iter().any(|root| path.starts_with(root)) {
+                return Err(BrowserError::PermissionDenied(format!(
+                    "{} is not in an approved upload location",
+                    file
+                )));
+            }
+
+            Ok(path.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+impl Drop for BrowserManager {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.chrome_process.take() {
+            let _ = child.kill();
+        }
+    }
+}
+
+// Global browser manager instance
+use once_cell::sync::Lazy;
+use std::sync::Mutex as StdMutex;
+
+static BROWSER_MANAGER: Lazy<StdMutex<Option<Arc<Mutex<BrowserManager>>>>> =
+    Lazy::new(|| StdMutex::new(None));
+
+/// The manager's tabs, shared so lookups on every keystroke don't queue behind
+/// commands holding the manager.
+static OPEN_TABS: Lazy<StdMutex<Option<SharedTabs>>> = Lazy::new(|| StdMutex::new(None));
+
+type SharedTabs = Arc<RwLock<HashMap<TabId, Tab>>>;
+
+pub fn init_browser_manager() -> Arc<Mutex<BrowserManager>> {
+    let manager = BrowserManager::new();
+    *OPEN_TABS.lock().unwrap() = Some(manager.tabs.clone());
+    let manager = Arc::new(Mutex::new(manager));
+    let mut global = BROWSER_MANAGER.lock().unwrap();
+    *global = Some(manager.clone());
+    manager
+}
+
+pub fn get_browser_manager() -> Option<Arc<Mutex<BrowserManager>>> {
+    BROWSER_MANAGER.lock().unwrap().clone()
+}
+
+/// The open tabs in no particular order, read without locking the manager.
+pub async fn open_tabs() -> Vec<Tab> {
+    let tabs = OPEN_TABS.lock().unwrap().clone();
+    match tabs {
+        Some(tabs) => tabs.read().await.values().cloned().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// How long a freshly launched Chrome gets to start serving DevTools.
+const CHROME_STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Launches Chrome and connects the manager to it.
+///
+/// The manager is only locked to spawn the process and to store the connection, so
+/// commands aren't held up while Chrome starts.
+pub async fn start_chrome(app_handle: tauri::AppHandle) -> Result<()> {
+    let manager = get_browser_manager().ok_or_else(|| {
+        BrowserError::Configuration("Browser manager not initialized".to_string())
+    })?;
+    let port = manager.lock().await.launch_chrome(&app_handle).await?;
+    connect_when_ready(&manager, port).await
+}
+
+/// Waits for DevTools on `port`, then connects the manager unless it already is.
+async fn connect_when_ready(manager: &Mutex<BrowserManager>, port: u16) -> Result<()> {
+    let ws_url = wait_for_browser_ws_url(port, CHROME_STARTUP_TIMEOUT).await?;
+
+    let manager = manager.lock().await;
+    if manager.cdp.read().await.is_some() {
+        return Ok(());
+    }
+    manager.connect_to(&ws_url).await?;
+    info!("Connected to Chrome on port {}", port);
+    Ok(())
+}
+
+/// Forwards browser-level `Target.targetInfoChanged` events to the manager.
+async fn watch_target_info(mut events: broadcast::Receiver<CdpEvent>) {
+    while let Some(event) = recv_event(&mut events).await {
+        if event.session_id.is_some() || event.method != "Target.targetInfoChanged" {
+            continue;
+        }
+        if let Some(manager) = get_browser_manager() {
+            manager
+                .lock()
+                .await
+                .handle_target_info_changed(&event.params["targetInfo"])
+                .await;
+        }
+    }
+}
+
+/// Puts the site's zoom back on every new document in a tab, which starts unzoomed, and
+/// forgets the tab's isolated world once the document it lived in is gone.
+///
+/// Zoom waits for `DOMContentLoaded`, when the new root element is there to style.
+async fn pump_navigations(
+    tab_id: TabId,
+    session: CdpSession,
+    zoom_levels: Arc<RwLock<HashMap<String, f64>>>,
+    worlds: IsolatedWorlds,
+    mut events: broadcast::Receiver<CdpEvent>,
+) {
+    let mut url = String::new();
+    while let Some(event) = recv_event(&mut events).await {
+        if !event.is_for(&session) {
+            continue;
+        }
+
+        match event.method.as_str() {
+            "Page.frameNavigated" if event.params["frame"]["parentId"].is_null() => {
+                url = event.params["frame"]["url"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                worlds.write().await.remove(&tab_id);
+            }
+            "Page.domContentEventFired" => {
+                let zoom = zoom_for_url(&*zoom_levels.read().await, &url);
+                if zoom == DEFAULT_ZOOM {
+                    continue;
+                }
+                if let Err(e) = apply_zoom(&worlds, &tab_id, &session, zoom).await {
+                    warn!("Failed to reapply zoom in tab {}: {}", tab_id, e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn tab_metrics(tab_id: &str, session: &CdpSession) -> Result<TabMetrics> {
+    session.send("Performance.enable", json!({})).await?;
+    let result = session.send("Performance.getMetrics", json!({})).await?;
+    Ok(TabMetrics::from_performance_metrics(tab_id, &result))
+}
+
+/// Reads the pid of a tab's renderer from the metadata of an empty trace, as DevTools
+/// doesn't report it on the target.
+async fn renderer_pid(session: &CdpSession) -> Result<Option<u32>> {
+    let mut events = session.subscribe();
+    session
+        .send(
+            "Tracing.start",
+            json!({
+                "categories": "disabled-by-default-devtools.timeline",
+                "transferMode": "ReportEvents",
+            }),
+        )
+        .await?;
+    session.send("Tracing.end", json!({})).await?;
+
+    let trace = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut trace = Vec::new();
+        while let Some(event) = recv_event(&mut events).await {
+            if !event.is_for(session) {
+                continue;
+            }
+            match event.method.as_str() {
+                "Tracing.dataCollected" => trace.extend(
+                    event.params["value"]
+                        .as_array()
+                        .cloned()
+                        .unwrap_or_default(),
+                ),
+                "Tracing.tracingComplete" => break,
+                _ => {}
+            }
+        }
+        trace
+    })
+    .await
+    .map_err(|_| BrowserError::Cdp("Timed out waiting for the trace".to_string()))?;
+
+    Ok(renderer_pid_from_trace(&trace))
+}
+
+async fn close_chrome_target(conn: &CdpConnection, tab_id: &str, target_id: &str) {
+    if let Err(e) = conn
+        .send(None, "Target.closeTarget", json!({ "targetId": target_id }))
+        .await
+    {
+        warn!("Failed to close target for tab {}: {}", tab_id, e);
+    }
+}
+
+/// Periodically freezes and discards idle background tabs.
+pub fn start_lifecycle_manager() {
+    tauri::async_runtime::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if let Some(manager) = get_browser_manager() {
+                sweep_idle_tabs(&manager).await;
+            }
+        }
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleAction {
+    Freeze,
+    Discard,
+}
+
+struct IdleTab {
+    tab_id: TabId,
+    state: LifecycleState,
+    action: IdleAction,
+    session: CdpSession,
+}
+
+/// Freezes or discards background tabs that have been idle past the configured limits.
+///
+/// The manager is only locked to pick the tabs and to record each outcome. The CDP
+/// round-trips in between run without it, so commands aren't held up by the sweep.
+pub async fn sweep_idle_tabs(manager: &Mutex<BrowserManager>) {
+    let idle = manager.lock().await.idle_tabs().await;
+    for tab in idle {
+        let result = match tab.action {
+            IdleAction::Freeze => freeze_tab(manager, &tab).await,
+            IdleAction::Discard => discard_tab(manager, &tab).await,
+        };
+        if let Err(e) = result {
+            warn!("Lifecycle transition failed for tab {}: {}", tab.tab_id, e);
+        }
+    }
+}
+
+async fn is_lifecycle_exempt(session: &CdpSession) -> bool {
+    let playing = session
+        .send(
+            "Runtime.evaluate",
+            json!({
+                "expression": "Array.from(document.querySelectorAll('audio, video')).some(m => !m.paused && !m.muted)",
+                "returnByValue": true,
+            }),
+        )
+        .await;
+
+    matches!(playing, Ok(result) if result["result"]["value"] == json!(true))
+}
+
+async fn freeze_tab(manager: &Mutex<BrowserManager>, tab: &IdleTab) -> Result<()> {
+    if is_lifecycle_exempt(&tab.session).await {
+        return Ok(());
+    }
+    tab.session
+        .send("Page.setWebLifecycleState", json!({ "state": "frozen" }))
+        .await?;
+
+    let manager = manager.lock().await;
+    if !manager.still_idle(&tab.tab_id, tab.state).await {
+        // The user switched to it meanwhile
+        drop(manager);
+        tab.session
+            .send("Page.setWebLifecycleState", json!({ "state": "active" }))
+            .await?;
+        return Ok(());
+    }
+    manager
+        .set_lifecycle_state(&tab.tab_id, LifecycleState::Frozen)
+        .await;
+
+    info!("Froze idle tab {}", tab.tab_id);
+    Ok(())
+}
+
+async fn discard_tab(manager: &Mutex<BrowserManager>, tab: &IdleTab) -> Result<()> {
+    // Frozen pages don't run script, so thaw first to read their final state. They were
+    // checked for playing media before they froze and can't have started since.
+    if tab.state == LifecycleState::Frozen {
+        tab.session
+            .send("Page.setWebLifecycleState", json!({ "state": "active" }))
+            .await?;
+    } else if is_lifecycle_exempt(&tab.session).await {
+        return Ok(());
+    }
+    let snapshot = tab
+        .session
+        .send(
+            "Runtime.evaluate",
+            json!({
+                "expression": "({ url: location.href, title: document.title, x: scrollX, y: scrollY })",
+                "returnByValue": true,
+            }),
+        )
+        .await?;
+    let snapshot = &snapshot["result"]["value"];
+
+    let target = {
+        let manager = manager.lock().await;
+        if !manager.still_idle(&tab.tab_id, tab.state).await {
+            return Ok(());
+        }
+        if let Some(t) = manager.tabs.write().await.get_mut(&tab.tab_id) {
+            if let Some(url) = snapshot["url"].as_str() {
+                t.url = url.to_string();
+            }
+            if let Some(title) = snapshot["title"].as_str().filter(|t| !t.is_empty()) {
+                t.title = title.to_string();
+            }
+        }
+        manager.discarded_scroll.write().await.insert(
+            tab.tab_id.clone(),
+            (
+                snapshot["x"].as_f64().unwrap_or_default(),
+                snapshot["y"].as_f64().unwrap_or_default(),
+            ),
+        );
+        let target = manager.detach_target(&tab.tab_id).await;
+        manager
+            .set_lifecycle_state(&tab.tab_id, LifecycleState::Discarded)
+            .await;
+        target
+    };
+    if let Some((conn, target_id)) = target {
+        close_chrome_target(&conn, &tab.tab_id, &target_id).await;
+    }
+
+    info!("Discarded idle tab {}", tab.tab_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleAction;
+    use crate::test_support::MockCdpServer;
+
+    async fn connected_manager() -> (BrowserManager, MockCdpServer) {
+        let server = MockCdpServer::start().await;
+        let manager = BrowserManager::new();
+        manager.connect_to(&server.ws_url()).await.unwrap();
+        (manager, server)
+    }
+
+    #[tokio::test]
+    async fn connecting_enables_target_discovery() {
+        let (_manager, server) = connected_manager().await;
+
+        let discover = server.requests("Target.setDiscoverTargets");
+        assert_eq!(discover.len(), 1);
+        assert_eq!(discover[0]["params"]["discover"], true);
+        assert_eq!(server.requests("Browser.setDownloadBehavior").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn chrome_is_found_through_its_devtools_port() {
+        let server = MockCdpServer::start().await;
+        let manager = Mutex::new(BrowserManager::new());
+
+        connect_when_ready(&manager, server.port()).await.unwrap();
+        // Connecting again, as a second launch would, keeps the first connection
+        connect_when_ready(&manager, server.port()).await.unwrap();
+        assert_eq!(server.requests("Target.setDiscoverTargets").len(), 1);
+
+        let manager = manager.lock().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        manager.session(&tab.id).await.unwrap();
+        manager
+            .navigate_tab(&tab.id, "https://a.test/")
+            .await
+            .unwrap();
+        assert_eq!(server.requests("Page.navigate").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn new_tab_becomes_active_and_deactivates_previous() {
+        let (manager, _server) = connected_manager().await;
+
+        let first = manager.create_tab(None, None).await.unwrap();
+        let second = manager
+            .create_tab(Some("https://example.com".to_string()), None)
+            .await
+            .unwrap();
+
+        let tabs = manager.get_tabs().await;
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[0].id, first.id);
+        assert!(!tabs[0].is_active);
+        assert!(tabs[1].is_active);
+        assert_eq!(manager.get_active_tab().await.unwrap().id, second.id);
+        assert_eq!(first.url, "about:blank");
+    }
+
+    #[tokio::test]
+    async fn session_attaches_to_a_new_target_once() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager
+            .create_tab(Some("https://example.com".to_string()), None)
+            .await
+            .unwrap();
+
+        let session = manager.session(&tab.id).await.unwrap();
+        let again = manager.session(&tab.id).await.unwrap();
+        assert_eq!(session.session_id, again.session_id);
+
+        let created = server.requests("Target.createTarget");
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0]["params"]["url"], "https://example.com");
+
+        let attached = server.requests("Target.attachToTarget");
+        assert_eq!(attached.len(), 1);
+        assert_eq!(
+            attached[0]["params"]["targetId"],
+            session.target_id.as_str()
+        );
+        assert_eq!(attached[0]["params"]["flatten"], true);
+    }
+
+    #[tokio::test]
+    async fn navigate_sends_page_navigate_on_the_tab_session() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+
+        // No target yet: only the stored URL changes
+        manager
+            .navigate_tab(&tab.id, "https://a.test/")
+            .await
+            .unwrap();
+        assert!(server.requests("Page.navigate").is_empty());
+
+        let session = manager.session(&tab.id).await.unwrap();
+        assert_eq!(
+            server.requests("Target.createTarget")[0]["params"]["url"],
+            "https://a.test/"
+        );
+
+        let navigated = manager
+            .navigate_tab(&tab.id, "https://b.test/")
+            .await
+            .unwrap();
+        assert_eq!(navigated.url, "https://b.test/");
+
+        let requests = server.requests("Page.navigate");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["sessionId"], session.session_id.as_str());
+        assert_eq!(requests[0]["params"]["url"], "https://b.test/");
+    }
+
+    #[tokio::test]
+    async fn closing_the_active_tab_closes_its_target_and_activates_another() {
+        let (manager, server) = connected_manager().await;
+        let first = manager.create_tab(None, None).await.unwrap();
+        let second = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&second.id).await.unwrap();
+
+        manager.close_tab(&second.id).await.unwrap();
+
+        let closed = server.requests("Target.closeTarget");
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0]["params"]["targetId"], session.target_id.as_str());
+
+        let active = manager.get_active_tab().await.unwrap();
+        assert_eq!(active.id, first.id);
+        assert!(active.is_active);
+        assert!(matches!(
+            manager.close_tab(&second.id).await,
+            Err(BrowserError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn process_info_ties_renderers_to_their_tabs() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        server.on("SystemInfo.getProcessInfo", |_| {
+            Ok(json!({ "processInfo": [
+                { "id": 100, "type": "browser", "cpuTime": 4.0 },
+                { "id": 200, "type": "renderer", "cpuTime": 1.5 },
+            ] }))
+        });
+        server.on("Performance.getMetrics", |_| {
+            Ok(json!({ "metrics": [{ "name": "JSHeapUsedSize", "value": 2048.0 }] }))
+        });
+
+        let trace = async {
+            server.wait_for_request("Tracing.end").await.unwrap();
+            let frames = json!([
+                { "frame": "main", "processId": 200 },
+                { "frame": "ad", "parent": "main", "processId": 300 },
+            ]);
+            server.emit(
+                "Tracing.dataCollected",
+                json!({ "value": [
+                    { "name": "TracingStartedInBrowser", "args": { "data": { "frames": frames } } },
+                ] }),
+                Some(&session.session_id),
+            );
+            server.emit(
+                "Tracing.tracingComplete",
+                json!({}),
+                Some(&session.session_id),
+            );
+        };
+        let (processes, _) = tokio::join!(manager.get_process_info(), trace);
+        let processes = processes.unwrap();
+
+        assert_eq!(processes.len(), 2);
+        assert!(processes[0].tab_ids.is_empty());
+        assert_eq!(processes[0].memory, None);
+        assert_eq!(processes[1].tab_ids, vec![tab.id]);
+        assert_eq!(processes[1].memory, Some(2048));
+    }
+
+    #[tokio::test]
+    async fn tab_metrics_leave_out_tabs_that_fail() {
+        let (manager, server) = connected_manager().await;
+        for _ in 0..2 {
+            let tab = manager.create_tab(None, None).await.unwrap();
+            manager.session(&tab.id).await.unwrap();
+        }
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        server.on("Performance.getMetrics", move |_| {
+            if calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed) == 0 {
+                return Err("Target crashed".to_string());
+            }
+            Ok(json!({ "metrics": [{ "name": "JSHeapUsedSize", "value": 2048.0 }] }))
+        });
+
+        let metrics = manager.get_tab_metrics().await.unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].js_heap_used_size, 2048);
+    }
+
+    #[tokio::test]
+    async fn killing_a_tab_without_a_target_opens_none() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+
+        assert!(matches!(
+            manager.kill_tab_process(&tab.id).await,
+            Err(BrowserError::NotFound(_))
+        ));
+        assert!(server.requests("Target.createTarget").is_empty());
+        assert!(server.requests("Page.crash").is_empty());
+    }
+
+    #[tokio::test]
+    async fn switching_to_a_frozen_tab_thaws_it() {
+        let (manager, server) = connected_manager().await;
+        let first = manager.create_tab(None, None).await.unwrap();
+        manager.create_tab(None, None).await.unwrap();
+        manager.session(&first.id).await.unwrap();
+        manager
+            .set_lifecycle_state(&first.id, LifecycleState::Frozen)
+            .await;
+
+        let tab = manager.switch_tab(&first.id).await.unwrap();
+        assert!(tab.is_active);
+        assert_eq!(tab.lifecycle_state, LifecycleState::Active);
+
+        let thawed = server.requests("Page.setWebLifecycleState");
+        assert_eq!(thawed.len(), 1);
+        assert_eq!(thawed[0]["params"]["state"], "active");
+    }
+
+    #[tokio::test]
+    async fn discarding_a_frozen_tab_thaws_it_without_the_media_check() {
+        let (manager, server) = connected_manager().await;
+        let first = manager
+            .create_tab(Some("https://example.com/".to_string()), None)
+            .await
+            .unwrap();
+        manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&first.id).await.unwrap();
+        manager
+            .set_lifecycle_state(&first.id, LifecycleState::Frozen)
+            .await;
+        manager
+            .set_lifecycle_settings(LifecycleSettings {
+                enabled: true,
+                freeze_after: Duration::ZERO,
+                discard_after: Duration::ZERO,
+            })
+            .await;
+        server.on("Runtime.evaluate", |_| {
+            Ok(json!({ "result": { "value": {
+                "url": "https://example.com/later", "title": "Later", "x": 0, "y": 320,
+            } } }))
+        });
+
+        let manager = Mutex::new(manager);
+        sweep_idle_tabs(&manager).await;
+
+        let thawed = server.requests("Page.setWebLifecycleState");
+        assert_eq!(thawed.len(), 1);
+        assert_eq!(thawed[0]["params"]["state"], "active");
+        // Only the snapshot: a frozen page can't answer the media check
+        assert_eq!(server.requests("Runtime.evaluate").len(), 1);
+        let closed = server.requests("Target.closeTarget");
+        assert_eq!(closed[0]["params"]["targetId"], session.target_id.as_str());
+
+        let manager = manager.lock().await;
+        let tab = manager.tabs.read().await[&first.id].clone();
+        assert_eq!(tab.lifecycle_state, LifecycleState::Discarded);
+        assert_eq!(tab.url, "https://example.com/later");
+        assert_eq!(
+            manager.discarded_scroll.read().await[&first.id],
+            (0.0, 320.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn switching_to_a_discarded_tab_reloads_it_and_restores_scroll() {
+        let (manager, server) = connected_manager().await;
+        let first = manager
+            .create_tab(Some("https://example.com/long".to_string()), None)
+            .await
+            .unwrap();
+        manager.create_tab(None, None).await.unwrap();
+        manager
+            .set_lifecycle_state(&first.id, LifecycleState::Discarded)
+            .await;
+        manager
+            .discarded_scroll
+            .write()
+            .await
+            .insert(first.id.clone(), (0.0, 640.0));
+
+        manager.switch_tab(&first.id).await.unwrap();
+        let session = manager.session(&first.id).await.unwrap();
+        assert_eq!(
+            server.requests("Target.createTarget")[0]["params"]["url"],
+            "about:blank"
+        );
+        assert_eq!(
+            server.requests("Page.navigate")[0]["params"]["url"],
+            "https://example.com/long"
+        );
+
+        server.emit("Page.loadEventFired", json!({}), Some(&session.session_id));
+        let scroll = server.wait_for_request("Runtime.evaluate").await.unwrap();
+        assert_eq!(scroll["params"]["expression"], "window.scrollTo(0, 640)");
+    }
+
+    #[tokio::test]
+    async fn commands_on_a_discarded_tab_wake_it_on_one_target() {
+        let (manager, server) = connected_manager().await;
+        let first = manager
+            .create_tab(Some("https://example.com/".to_string()), None)
+            .await
+            .unwrap();
+        manager.create_tab(None, None).await.unwrap();
+        manager
+            .set_lifecycle_state(&first.id, LifecycleState::Discarded)
+            .await;
+
+        let session = manager.session(&first.id).await.unwrap();
+        let woken = manager.tabs.read().await.get(&first.id).cloned().unwrap();
+        assert_eq!(woken.lifecycle_state, LifecycleState::Active);
+        assert_eq!(
+            server.requests("Page.navigate")[0]["params"]["url"],
+            "https://example.com/"
+        );
+
+        manager.switch_tab(&first.id).await.unwrap();
+        assert_eq!(server.requests("Target.createTarget").len(), 1);
+        assert_eq!(
+            manager.session(&first.id).await.unwrap().session_id,
+            session.session_id
+        );
+    }
+
+    #[tokio::test]
+    async fn target_info_changes_update_the_tab() {
+        let (manager, _server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+
+        manager
+            .handle_target_info_changed(&json!({
+                "targetId": session.target_id,
+                "url": "https://example.com/page",
+                "title": "Example",
+            }))
+            .await;
+
+        let tab = manager.get_active_tab().await.unwrap();
+        assert_eq!(tab.url, "https://example.com/page");
+        assert_eq!(tab.title, "Example");
+    }
+
+    /// The `targetInfo` of the `Target.targetCreated` Chrome sends for a popup.
+    fn popup_created(opener: &CdpSession, target_id: &str, url: &str) -> Value {
+        json!({
+            "targetInfo": {
+                "targetId": target_id,
+                "type": "page",
+                "title": "",
+                "url": url,
+                "attached": false,
+                "openerId": opener.target_id,
+            }
+        })
+    }
+
+    async fn manager_with_opener(
+        policy: Option<PopupPolicy>,
+    ) -> (BrowserManager, MockCdpServer, Tab, CdpSession, Tab) {
+        let (manager, server) = connected_manager().await;
+        let opener = manager
+            .create_tab(Some("https://www.news.test/story".to_string()), None)
+            .await
+            .unwrap();
+        let next = manager.create_tab(None, None).await.unwrap();
+        manager.switch_tab(&opener.id).await.unwrap();
+        let session = manager.session(&opener.id).await.unwrap();
+        if let Some(policy) = policy {
+            manager
+                .set_popup_rules(vec![PopupRule {
+                    host: "news.test".to_string(),
+                    policy,
+                }])
+                .await;
+        }
+        (manager, server, opener, session, next)
+    }
+
+    #[tokio::test]
+    async fn allowed_popups_open_as_tabs_next_to_their_opener() {
+        let (manager, server, opener, session, next) = manager_with_opener(None).await;
+        let created = popup_created(&session, "popup-1", "https://news.test/share");
+
+        manager.handle_popup(&created["targetInfo"]).await.unwrap();
+
+        let tabs = manager.get_tabs().await;
+        assert_eq!(tabs.len(), 3);
+        let popup = &tabs[1];
+        assert_eq!(tabs[0].id, opener.id);
+        assert_eq!(tabs[2].id, next.id);
+        assert_eq!(popup.opener_id.as_deref(), Some(opener.id.as_str()));
+        assert_eq!(popup.url, "https://news.test/share");
+        assert_eq!(popup.position, 1);
+        assert_eq!(tabs[2].position, 2);
+        assert!(popup.is_active);
+        assert!(!tabs[0].is_active);
+
+        // Chrome's own target becomes the tab's, rather than a new one
+        assert_eq!(
+            manager.session(&popup.id).await.unwrap().target_id,
+            "popup-1"
+        );
+        assert_eq!(server.requests("Target.createTarget").len(), 1);
+        assert!(server.requests("Target.closeTarget").is_empty());
+    }
+
+    #[tokio::test]
+    async fn blocked_popups_are_closed_without_a_tab() {
+        let (manager, server, _opener, session, _next) =
+            manager_with_opener(Some(PopupPolicy::Block)).await;
+        let created = popup_created(&session, "popup-1", "https://ads.test/");
+
+        manager.handle_popup(&created["targetInfo"]).await.unwrap();
+
+        assert_eq!(manager.get_tabs().await.len(), 2);
+        let closed = server.requests("Target.closeTarget");
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0]["params"]["targetId"], "popup-1");
+    }
+
+    #[tokio::test]
+    async fn redirected_popups_load_in_their_opener() {
+        let (manager, server, opener, session, _next) =
+            manager_with_opener(Some(PopupPolicy::Redirect)).await;
+        server.on("Target.getTargetInfo", |_| {
+            Ok(json!({ "targetInfo": { "url": "https://news.test/next" } }))
+        });
+        // window.open targets start out blank
+        let created = popup_created(&session, "popup-1", "about:blank");
+
+        manager.handle_popup(&created["targetInfo"]).await.unwrap();
+
+        assert_eq!(manager.get_tabs().await.len(), 2);
+        assert_eq!(
+            server.requests("Target.closeTarget")[0]["params"]["targetId"],
+            "popup-1"
+        );
+        let navigated = server.requests("Page.navigate");
+        assert_eq!(navigated[0]["sessionId"], session.session_id.as_str());
+        assert_eq!(navigated[0]["params"]["url"], "https://news.test/next");
+        let opener = manager.tabs.read().await.get(&opener.id).cloned().unwrap();
+        assert_eq!(opener.url, "https://news.test/next");
+    }
+
+    #[tokio::test]
+    async fn popups_from_targets_we_dont_manage_are_left_alone() {
+        let (manager, server, _opener, _session, _next) =
+            manager_with_opener(Some(PopupPolicy::Block)).await;
+        let created = json!({
+            "targetId": "popup-1",
+            "type": "page",
+            "url": "https://ads.test/",
+            "openerId": "extension-page",
+        });
+
+        manager.handle_popup(&created).await.unwrap();
+
+        assert_eq!(manager.get_tabs().await.len(), 2);
+        assert!(server.requests("Target.closeTarget").is_empty());
+    }
+
+    #[tokio::test]
+    async fn zoom_is_remembered_per_origin_and_reapplied() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager
+            .create_tab(Some("https://example.com/a".to_string()), None)
+            .await
+            .unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        server.on("Page.getFrameTree", |_| {
+            Ok(json!({ "frameTree": { "frame": { "id": "main-frame" } } }))
+        });
+        server.on("Page.createIsolatedWorld", |_| {
+            Ok(json!({ "executionContextId": 9 }))
+        });
+        let zooms = || server.requests("Runtime.evaluate");
+        let worlds = || server.requests("Page.createIsolatedWorld").len();
+
+        let zoom = manager.zoom_tab(&tab.id, ZoomAction::In).await.unwrap();
+        assert_eq!(zoom.origin, "https://example.com");
+        assert_eq!(zoom.level, 1.1);
+        let zoom = manager.zoom_tab(&tab.id, ZoomAction::In).await.unwrap();
+        assert_eq!(zoom.level, 1.25);
+        assert_eq!(worlds(), 1);
+
+        // A title change leaves the page alone
+        manager
+            .handle_target_info_changed(&json!({
+                "targetId": session.target_id,
+                "url": "https://example.com/a",
+                "title": "Example",
+            }))
+            .await;
+        assert_eq!(zooms().len(), 2);
+
+        // Same-origin navigation gets the zoom back once the new document is parsed
+        server.emit(
+            "Page.frameNavigated",
+            json!({ "frame": { "id": "main-frame", "url": "https://example.com/b" } }),
+            Some(&session.session_id),
+        );
+        server.emit(
+            "Page.domContentEventFired",
+            json!({}),
+            Some(&session.session_id),
+        );
+        for _ in 0..100 {
+            if zooms().len() == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let scales = zooms();
+        assert_eq!(scales.len(), 3);
+        assert_eq!(
+            scales[2]["params"]["expression"],
+            "document.documentElement.style.zoom = \"1.25\""
+        );
+        assert_eq!(scales[2]["params"]["contextId"], 9);
+        assert_eq!(worlds(), 2);
+
+        // A new tab on the same origin opens zoomed
+        let other = manager
+            .create_tab(Some("https://example.com/c".to_string()), None)
+            .await
+            .unwrap();
+        let other_session = manager.session(&other.id).await.unwrap();
+        assert_eq!(zooms()[3]["sessionId"], other_session.session_id.as_str());
+
+        let zoom = manager.zoom_tab(&tab.id, ZoomAction::Reset).await.unwrap();
+        assert_eq!(zoom.level, DEFAULT_ZOOM);
+        assert!(manager.zoom_levels.read().await.is_empty());
+        assert_eq!(
+            zooms().last().unwrap()["params"]["expression"],
+            "document.documentElement.style.zoom = \"\""
+        );
+
+        manager.navigate_tab(&tab.id, "about:blank").await.unwrap();
+        assert!(matches!(
+            manager.zoom_tab(&tab.id, ZoomAction::In).await,
+            Err(BrowserError::Configuration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn zooming_out_from_the_default_goes_below_100_percent() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager
+            .create_tab(Some("https://example.com/".to_string()), None)
+            .await
+            .unwrap();
+        manager.session(&tab.id).await.unwrap();
+        server.on("Page.createIsolatedWorld", |_| {
+            Ok(json!({ "executionContextId": 9 }))
+        });
+
+        let zoom = manager.zoom_tab(&tab.id, ZoomAction::Out).await.unwrap();
+        assert!(zoom.level < DEFAULT_ZOOM);
+        assert_eq!(zoom.level, 0.9);
+        for _ in 0..10 {
+            manager.zoom_tab(&tab.id, ZoomAction::Out).await.unwrap();
+        }
+        assert_eq!(manager.zoom_for("https://example.com/").await, 0.25);
+    }
+
+    #[tokio::test]
+    async fn site_data_lists_cookie_and_tab_origins_with_usage() {
+        let (manager, server) = connected_manager().await;
+        manager
+            .create_tab(Some("http://localhost:3000/app".to_string()), None)
+            .await
+            .unwrap();
+        server.on("Storage.getCookies", |_| {
+            Ok(json!({ "cookies": [
+                { "name": "a", "domain": ".example.com", "secure": true },
+                { "name": "b", "domain": "example.com", "sourceScheme": "Secure", "sourcePort": 443 },
+                { "name": "c", "domain": "empty.test", "sourceScheme": "NonSecure" },
+                { "name": "d", "domain": "dev.test", "secure": true, "sourcePort": 8443 },
+                { "name": "e", "domain": "broken.test" },
+            ] }))
+        });
+        server.on("Storage.getUsageAndQuota", |params| {
+            if params["origin"] == "http://broken.test" {
+                return Err("Storage partition not found".to_string());
+            }
+            let usage = match params["origin"].as_str() {
+                Some("http://localhost:3000") => 4096.0,
+                Some("https://example.com") => 512.0,
+                _ => 0.0,
+            };
+            Ok(json!({
+                "usage": usage,
+                "quota": 1e9,
+                "usageBreakdown": [
+                    { "storageType": "indexeddb", "usage": usage },
+                    { "storageType": "cache_storage", "usage": 0 },
+                ],
+            }))
+        });
+
+        let sites = manager.get_site_data().await.unwrap();
+        let origins: Vec<&str> = sites.iter().map(|s| s.origin.as_str()).collect();
+        assert_eq!(
+            origins,
+            [
+                "http://localhost:3000",
+                "https://example.com",
+                "http://broken.test",
+                "http://empty.test",
+                "https://dev.test:8443",
+            ]
+        );
+        assert_eq!(sites[1].cookie_count, 2);
+        assert_eq!(sites[0].usage_breakdown.len(), 1);
+        // One origin failing doesn't hide the others
+        assert!(sites[2].usage_unavailable);
+        assert_eq!(sites[2].cookie_count, 1);
+        assert!(!sites[3].usage_unavailable);
+
+        let origin = manager
+            .clear_site_data("https://example.com/login", &[])
+            .await
+            .unwrap();
+        assert_eq!(origin, "https://example.com");
+        let cleared = server.requests("Storage.clearDataForOrigin");
+        assert_eq!(cleared[0]["params"]["origin"], "https://example.com");
+        assert_eq!(
+            cleared[0]["params"]["storageTypes"],
+            "cookies,local_storage,indexeddb,cache_storage,service_workers"
+        );
+    }
+
+    /// Emits what Chrome sends when the main frame runs into a bad certificate.
+    fn fail_navigation_on_certificate(
+        server: &MockCdpServer,
+        session: &CdpSession,
+        event_id: u64,
+        url: &str,
+    ) {
+        let request_id = format!("n{}", event_id);
+        server.emit(
+            "Network.requestWillBeSent",
+            json!({
+                "requestId": request_id,
+                "type": "Document",
+                "frameId": session.target_id,
+                "request": { "url": url },
+            }),
+            Some(&session.session_id),
+        );
+        server.emit(
+            "Security.certificateError",
+            json!({ "eventId": event_id, "errorType": "net::ERR_CERT_DATE_INVALID", "requestURL": url }),
+            Some(&session.session_id),
+        );
+        server.emit(
+            "Network.loadingFailed",
+            json!({ "requestId": request_id, "errorText": "net::ERR_ABORTED" }),
+            Some(&session.session_id),
+        );
+    }
+
+    async fn certificate_decisions(server: &MockCdpServer, count: usize) -> Vec<(u64, String)> {
+        for _ in 0..100 {
+            if server.requests("Security.handleCertificateError").len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server
+            .requests("Security.handleCertificateError")
+            .into_iter()
+            .map(|r| {
+                let params = &r["params"];
+                let action = params["action"].as_str().unwrap_or_default().to_string();
+                (params["eventId"].as_u64().unwrap_or_default(), action)
+            })
+            .collect()
+    }
+
+    async fn blocked_certificate(manager: &BrowserManager, tab_id: &str) -> CertificateError {
+        for _ in 0..100 {
+            let tab = manager.tabs.read().await.get(tab_id).cloned().unwrap();
+            if let Some(error) = tab.security.certificate_error {
+                return error;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("tab {} was never blocked", tab_id);
+    }
+
+    #[tokio::test]
+    async fn certificate_errors_block_until_the_user_proceeds() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        assert_eq!(
+            server.requests("Security.setOverrideCertificateErrors")[0]["params"]["override"],
+            true
+        );
+
+        fail_navigation_on_certificate(&server, &session, 1, "https://expired.test/login");
+        let blocked = blocked_certificate(&manager, &tab.id).await;
+        assert_eq!(blocked.host, "expired.test");
+        assert_eq!(blocked.error_type, "net::ERR_CERT_DATE_INVALID");
+        assert_eq!(
+            certificate_decisions(&server, 1).await,
+            [(1, "cancel".to_string())]
+        );
+
+        let error = manager.proceed_certificate_error(&tab.id).await.unwrap();
+        assert_eq!(error.host, "expired.test");
+        assert_eq!(
+            server.requests("Page.navigate")[0]["params"]["url"],
+            "https://expired.test/login"
+        );
+        assert!(manager.proceed_certificate_error(&tab.id).await.is_err());
+
+        server.emit(
+            "Security.certificateError",
+            json!({ "eventId": 2, "errorType": "net::ERR_CERT_DATE_INVALID", "requestURL": "https://expired.test/login" }),
+            Some(&session.session_id),
+        );
+        assert_eq!(
+            certificate_decisions(&server, 2).await[1],
+            (2, "continue".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn proceeding_on_one_host_leaves_other_bad_hosts_blocked() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+
+        fail_navigation_on_certificate(&server, &session, 1, "https://expired.test/");
+        blocked_certificate(&manager, &tab.id).await;
+        manager.proceed_certificate_error(&tab.id).await.unwrap();
+
+        // A subresource from another bad host on the page that was let through
+        server.emit(
+            "Security.certificateError",
+            json!({ "eventId": 2, "errorType": "net::ERR_CERT_AUTHORITY_INVALID", "requestURL": "https://cdn.bad.test/app.js" }),
+            Some(&session.session_id),
+        );
+        fail_navigation_on_certificate(&server, &session, 3, "https://other.test/");
+
+        let blocked = blocked_certificate(&manager, &tab.id).await;
+        assert_eq!(blocked.host, "other.test");
+        assert_eq!(
+            certificate_decisions(&server, 3).await,
+            [
+                (1, "cancel".to_string()),
+                (2, "cancel".to_string()),
+                (3, "cancel".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn visible_security_state_is_reported_on_the_tab() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+
+        server.emit(
+            "Security.visibleSecurityStateChanged",
+            json!({ "visibleSecurityState": {
+                "securityState": "secure",
+                "securityStateIssueIds": ["displayed-mixed-content"],
+                "certificateSecurityState": {
+                    "subjectName": "example.com",
+                    "issuer": "Test CA",
+                    "validFrom": 1700000000.0,
+                    "validTo": 1800000000.0,
+                    "protocol": "TLS 1.3",
+                    "keyExchange": "",
+                    "cipher": "AES_128_GCM",
+                },
+            } }),
+            Some(&session.session_id),
+        );
+
+        let mut security = TabSecurity::default();
+        for _ in 0..100 {
+            security = manager.get_active_tab().await.unwrap().security;
+            if security.certificate.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(security.state, crate::security::SecurityState::Secure);
+        assert!(security.mixed_content);
+        assert_eq!(security.certificate.unwrap().issuer, "Test CA");
+    }
+
+    #[tokio::test]
+    async fn marked_screenshots_number_visible_elements_and_resolve_clicks() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        server.on("Page.getLayoutMetrics", |_| {
+            Ok(json!({ "cssLayoutViewport": { "clientWidth": 800, "clientHeight": 600 } }))
+        });
+        server.on("DOM.getDocument", |_| {
+            Ok(json!({ "root": { "nodeId": 1 } }))
+        });
+        server.on("DOM.querySelectorAll", |_| {
+            Ok(json!({ "nodeIds": [10, 11, 12, 13] }))
+        });
+        server.on("DOM.getBoxModel", |params| {
+            let quad = |x: f64, y: f64| json!([x, y, x + 80.0, y, x + 80.0, y + 20.0, x, y + 20.0]);
+            match params["nodeId"]
+                .as_i64()
+                .or(params["backendNodeId"].as_i64())
+            {
+                Some(10) => Ok(json!({ "model": { "border": quad(10.0, 10.0) } })),
+                Some(11) => Err("Could not compute box model.".to_string()),
+                Some(12) => Ok(json!({ "model": { "border": quad(10.0, 900.0) } })),
+                Some(13) => Ok(json!({ "model": { "border": quad(100.0, 50.0) } })),
+                // After scrolling, node 113 sits further up
+                Some(113) => Ok(json!({ "model": { "border": quad(100.0, 30.0) } })),
+                _ => Err("No node".to_string()),
+            }
+        });
+        server.on("DOM.describeNode", |params| {
+            let id = params["nodeId"].as_i64().unwrap();
+            Ok(json!({ "node": {
+                "backendNodeId": id + 100,
+                "localName": if id == 10 { "a" } else { "input" },
+                "attributes": ["type", "search", "placeholder", "Search docs"],
+            } }))
+        });
+        server.on("Page.captureScreenshot", |_| {
+            Ok(json!({ "data": "iVBORw0KGgo=" }))
+        });
+        server.on("Page.createIsolatedWorld", |_| {
+            Ok(json!({ "executionContextId": 7 }))
+        });
+
+        let shot = manager.capture_marked_screenshot(&tab.id).await.unwrap();
+        assert_eq!(shot.image, "iVBORw0KGgo=");
+        let numbered: Vec<(u32, i64)> = shot
+            .marks
+            .iter()
+            .map(|m| (m.number, m.backend_node_id))
+            .collect();
+        assert_eq!(numbered, [(1, 110), (2, 113)]);
+        assert_eq!(shot.marks[1].label.as_deref(), Some("Search docs"));
+
+        let evaluated = server.requests("Runtime.evaluate");
+        assert_eq!(evaluated.len(), 2);
+        assert_eq!(evaluated[1]["params"]["expression"], REMOVE_OVERLAY);
+
+        let mark = manager.click_mark(&tab.id, 2).await.unwrap();
+        assert_eq!(mark.y, 30.0);
+        let clicks = server.requests("Input.dispatchMouseEvent");
+        assert_eq!(clicks.len(), 2);
+        assert_eq!(clicks[0]["params"]["x"], 140.0);
+        assert_eq!(clicks[0]["params"]["y"], 40.0);
+        assert!(manager.click_mark(&tab.id, 3).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn restored_tabs_keep_their_order_and_active_tab() {
+        let (manager, server) = connected_manager().await;
+        let saved = |id: &str, position: i32, is_active: bool| Tab {
+            id: id.to_string(),
+            url: format!("https://{}.test/", id),
+            title: id.to_string(),
+            favicon: None,
+            window_id: MAIN_WINDOW.to_string(),
+            position,
+            is_active,
+            lifecycle_state: LifecycleState::Active,
+            opener_id: None,
+            browser_context_id: None,
+            group_id: None,
+            pinned: false,
+            security: TabSecurity::default(),
+        };
+
+        manager
+            .restore_tabs(
+                vec![
+                    saved("a", 0, false),
+                    saved("b", 1, true),
+                    saved("c", 2, false),
+                ],
+                Vec::new(),
+                Vec::new(),
+            )
+            .await;
+
+        let ids: Vec<String> = manager.get_tabs().await.into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(manager.get_active_tab().await.unwrap().id, "b");
+        // Nothing loads until a tab is used
+        assert!(server.requests("Target.createTarget").is_empty());
+
+        manager.session("c").await.unwrap();
+        assert_eq!(
+            server.requests("Target.createTarget")[0]["params"]["url"],
+            "https://c.test/"
+        );
+    }
+
+    #[tokio::test]
+    async fn opening_a_tab_set_can_replace_the_current_tabs() {
+        let (manager, _server) = connected_manager().await;
+        let old = manager.create_tab(None, None).await.unwrap();
+        let saved = |url: &str| SessionTab {
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon: None,
+        };
+
+        let merged = manager
+            .open_tab_set(vec![saved("https://a.test/")], false)
+            .await
+            .unwrap();
+        assert_eq!(manager.get_tabs().await.len(), 2);
+        assert_eq!(manager.get_active_tab().await.unwrap().id, merged[0].id);
+
+        let replaced = manager
+            .open_tab_set(
+                vec![saved("https://b.test/"), saved("https://c.test/")],
+                true,
+            )
+            .await
+            .unwrap();
+        let urls: Vec<String> = manager
+            .get_tabs()
+            .await
+            .into_iter()
+            .map(|t| t.url)
+            .collect();
+        assert_eq!(urls, ["https://b.test/", "https://c.test/"]);
+        assert!(replaced[0].is_active);
+        assert!(manager.get_recently_closed_tabs().await.is_empty());
+        assert!(matches!(
+            manager.switch_tab(&old.id).await,
+            Err(BrowserError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn moving_and_closing_tabs_keeps_positions_dense() {
+        let manager = BrowserManager::new();
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(manager.create_tab(None, None).await.unwrap().id);
+        }
+
+        manager.close_tab(&ids[1]).await.unwrap();
+        let tab = manager.create_tab(None, None).await.unwrap();
+        assert_eq!(tab.position, 3);
+
+        let moved = manager.move_tab(&tab.id, 0).await.unwrap();
+        let order: Vec<&str> = moved.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(order, [tab.id.as_str(), &ids[0], &ids[2], &ids[3]]);
+        let positions: Vec<i32> = moved.iter().map(|t| t.position).collect();
+        assert_eq!(positions, [0, 1, 2, 3]);
+
+        let moved = manager.move_tab(&ids[0], 99).await.unwrap();
+        assert_eq!(moved.last().unwrap().id, ids[0]);
+    }
+
+    #[tokio::test]
+    async fn groups_stay_together_as_tabs_move() {
+        let manager = BrowserManager::new();
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            ids.push(manager.create_tab(None, None).await.unwrap().id);
+        }
+        let order = |tabs: &[Tab]| {
+            tabs.iter()
+                .map(|t| ids.iter().position(|id| *id == t.id).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let group = manager
+            .create_tab_group(
+                &[ids[3].clone(), ids[1].clone()],
+                "Work".into(),
+                GroupColor::Blue,
+            )
+            .await
+            .unwrap();
+        let tabs = manager.get_tabs().await;
+        assert_eq!(order(&tabs), [0, 1, 3, 2, 4]);
+        assert_eq!(tabs[2].group_id.as_ref(), Some(&group.id));
+
+        // Dropped between two members, an ungrouped tab joins the group
+        let tabs = manager.move_tab(&ids[4], 2).await.unwrap();
+        assert_eq!(tabs[2].group_id.as_ref(), Some(&group.id));
+
+        // A whole group can't be dropped inside another one
+        let tabs = manager.move_tab_group(&group.id, 0).await.unwrap();
+        assert_eq!(order(&tabs), [1, 4, 3, 0, 2]);
+
+        let collapsed = manager
+            .update_tab_group(
+                &group.id,
+                TabGroupUpdate {
+                    collapsed: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(collapsed.collapsed);
+        assert_eq!(collapsed.title, "Work");
+
+        // Dragged away from its group, a tab leaves it; closing the rest removes the group
+        let tabs = manager.move_tab(&ids[1], 4).await.unwrap();
+        assert_eq!(tabs[4].group_id, None);
+        manager.close_tab(&ids[4]).await.unwrap();
+        manager.close_tab(&ids[3]).await.unwrap();
+        assert!(manager.get_tab_groups().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pinned_tabs_stay_in_front_and_survive_replacing_tabs() {
+        let manager = BrowserManager::new();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(manager.create_tab(None, None).await.unwrap().id);
+        }
+
+        let pinned = manager.set_tab_pinned(&ids[2], true).await.unwrap();
+        assert_eq!(pinned.position, 0);
+
+        // Unpinned tabs can't be moved in among the pinned ones
+        let tabs = manager.move_tab(&ids[1], 0).await.unwrap();
+        assert_eq!(tabs[0].id, ids[2]);
+        assert_eq!(tabs[1].id, ids[1]);
+
+        let saved = SessionTab {
+            url: "https://a.test/".to_string(),
+            title: "A".to_string(),
+            favicon: None,
+        };
+        manager.open_tab_set(vec![saved], true).await.unwrap();
+        let tabs = manager.get_tabs().await;
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[0].id, ids[2]);
+
+        let unpinned = manager.set_tab_pinned(&ids[2], false).await.unwrap();
+        assert_eq!(unpinned.position, 0);
+        assert!(!unpinned.pinned);
+    }
+
+    #[tokio::test]
+    async fn reopening_a_closed_tab_restores_its_place_and_history() {
+        let (manager, server) = connected_manager().await;
+        server.on("Page.getNavigationHistory", |_| {
+            Ok(json!({
+                "currentIndex": 1,
+                "entries": [
+                    { "url": "https://a.test/", "title": "A" },
+                    { "url": "https://b.test/", "title": "B" },
+                ],
+            }))
+        });
+        let first = manager.create_tab(None, None).await.unwrap();
+        let second = manager
+            .create_tab(Some("https://b.test/".to_string()), None)
+            .await
+            .unwrap();
+        manager.create_tab(None, None).await.unwrap();
+        manager.session(&second.id).await.unwrap();
+
+        manager.close_tab(&second.id).await.unwrap();
+        manager.close_tab(&first.id).await.unwrap();
+        let closed = manager.get_recently_closed_tabs().await;
+        assert_eq!(closed[0].id, first.id);
+        assert_eq!(closed[1].history.len(), 2);
+
+        let reopened = manager.reopen_closed_tab(Some(&second.id)).await.unwrap();
+        assert_eq!(reopened.tab.url, "https://b.test/");
+        assert_eq!(reopened.tab.position, 1);
+        assert!(reopened.tab.is_active);
+        assert_eq!(reopened.history_index, 1);
+        assert_eq!(reopened.history[0].url, "https://a.test/");
+
+        // Tabs that never loaded keep just their own URL
+        let reopened = manager.reopen_closed_tab(None).await.unwrap();
+        assert_eq!(reopened.tab.position, 0);
+        assert_eq!(reopened.history.len(), 1);
+        assert!(matches!(
+            manager.reopen_closed_tab(None).await,
+            Err(BrowserError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn closing_the_active_tab_activates_by_policy() {
+        let manager = BrowserManager::new();
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(manager.create_tab(None, None).await.unwrap().id);
+        }
+        manager.switch_tab(&ids[0]).await.unwrap();
+        manager.switch_tab(&ids[2]).await.unwrap();
+
+        let recent = manager.get_recent_tabs().await;
+        let recent: Vec<&str> = recent.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(recent, [&ids[2], &ids[0], &ids[3], &ids[1]]);
+
+        manager.close_tab(&ids[2]).await.unwrap();
+        assert_eq!(manager.get_active_tab().await.unwrap().id, ids[0]);
+
+        manager.set_activation_policy(ActivationPolicy::Right).await;
+        manager.close_tab(&ids[0]).await.unwrap();
+        assert_eq!(manager.get_active_tab().await.unwrap().id, ids[1]);
+        manager.switch_tab(&ids[3]).await.unwrap();
+        manager.close_tab(&ids[3]).await.unwrap();
+        assert_eq!(manager.get_active_tab().await.unwrap().id, ids[1]);
+    }
+
+    #[tokio::test]
+    async fn tabs_move_between_windows_keeping_each_active_tab() {
+        let manager = BrowserManager::new();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(manager.create_tab(None, None).await.unwrap().id);
+        }
+
+        let window = manager.create_window(Some(&ids[2])).await.unwrap();
+        let window_tabs = |tabs: Vec<Tab>| -> Vec<(String, i32, bool)> {
+            tabs.into_iter()
+                .map(|t| (t.id, t.position, t.is_active))
+                .collect()
+        };
+        assert_eq!(
+            window_tabs(manager.get_window_tabs(MAIN_WINDOW).await),
+            [(ids[0].clone(), 0, false), (ids[1].clone(), 1, true)]
+        );
+        assert_eq!(
+            window_tabs(manager.get_window_tabs(&window.id).await),
+            [(ids[2].clone(), 0, true)]
+        );
+        assert_eq!(manager.get_active_tab().await.unwrap().id, ids[2]);
+
+        // New tabs open in the focused window, and switching only touches its own window
+        let fourth = manager.create_tab(None, None).await.unwrap();
+        assert_eq!(
+            (fourth.window_id.as_str(), fourth.position),
+            (window.id.as_str(), 1)
+        );
+        manager.switch_tab(&ids[0]).await.unwrap();
+        let windows = manager.get_windows().await;
+        assert_eq!(windows[0].active_tab.as_ref(), Some(&ids[0]));
+        assert_eq!(windows[1].active_tab.as_ref(), Some(&fourth.id));
+
+        manager
+            .move_tab_to_window(&ids[1], &window.id, Some(0))
+            .await
+            .unwrap();
+        let moved = manager.get_window_tabs(&window.id).await;
+        let moved: Vec<&str> = moved.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(moved, [&ids[1], &ids[2], &fourth.id]);
+        assert!(manager.close_window(MAIN_WINDOW).await.is_err());
+
+        // A window closes with its last tab
+        manager.close_window(&window.id).await.unwrap();
+        let windows = manager.get_windows().await;
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].active_tab.as_ref(), Some(&ids[0]));
+        assert_eq!(manager.get_active_tab().await.unwrap().id, ids[0]);
+    }
+
+    #[tokio::test]
+    async fn find_skips_frames_that_fail() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        server.on("Page.getFrameTree", |_| {
+            Ok(json!({ "frameTree": {
+                "frame": { "id": "main" },
+                "childFrames": [{ "frame": { "id": "gone" } }, { "frame": { "id": "ad" } }],
+            } }))
+        });
+        server.on("Page.createIsolatedWorld", |params| {
+            match params["frameId"].as_str() {
+                Some("main") => Ok(json!({ "executionContextId": 1 })),
+                Some("gone") => Err("No frame for given id found".to_string()),
+                _ => Ok(json!({ "executionContextId": 3 })),
+            }
+        });
+        server.on("Runtime.evaluate", |params| {
+            if params["contextId"] == 3 {
+                return Err("Execution context was destroyed".to_string());
+            }
+            Ok(json!({ "result": { "value": 2 } }))
+        });
+
+        let result = manager
+            .find_in_page(&tab.id, "needle", false)
+            .await
+            .unwrap();
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.current_index, Some(0));
+        assert_eq!(server.requests("Page.createIsolatedWorld").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn evaluate_reports_values_and_exceptions() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        server.on("Runtime.evaluate", |params| {
+            if params["expression"] == "1 + 1" {
+                Ok(json!({ "result": { "type": "number", "value": 2 } }))
+            } else {
+                Ok(json!({
+                    "result": { "type": "object" },
+                    "exceptionDetails": {
+                        "text": "Uncaught",
+                        "lineNumber": 0,
+                        "columnNumber": 6,
+                        "exception": { "className": "Error", "description": "Error: boom" },
+                    },
+                }))
+            }
+        });
+
+        let ok = manager
+            .evaluate(&tab.id, "1 + 1", ScriptWorld::Main)
+            .await
+            .unwrap();
+        assert_eq!(ok.value, Some(json!(2)));
+        assert!(ok.exception.is_none());
+
+        let thrown = manager
+            .evaluate(&tab.id, "throw new Error('boom')", ScriptWorld::Main)
+            .await
+            .unwrap();
+        assert!(thrown.value.is_none());
+        assert_eq!(thrown.exception.unwrap().message, "Error: boom");
+    }
+
+    #[tokio::test]
+    async fn isolated_scripts_share_one_world_until_the_page_navigates() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        server.on("Page.getFrameTree", |_| {
+            Ok(json!({ "frameTree": { "frame": { "id": "main-frame", "loaderId": "doc-1" } } }))
+        });
+        server.on("Page.createIsolatedWorld", |_| {
+            Ok(json!({ "executionContextId": 42 }))
+        });
+        let evaluate = || manager.evaluate(&tab.id, "document.title", ScriptWorld::Isolated);
+
+        evaluate().await.unwrap();
+        evaluate().await.unwrap();
+        let world = server.requests("Page.createIsolatedWorld");
+        assert_eq!(world.len(), 1);
+        assert_eq!(world[0]["params"]["frameId"], "main-frame");
+        assert_eq!(world[0]["params"]["worldName"], ISOLATED_WORLD_NAME);
+        assert!(server
+            .requests("Runtime.evaluate")
+            .iter()
+            .all(|r| r["params"]["contextId"] == 42));
+
+        // The world went with the old document
+        server.emit(
+            "Page.frameNavigated",
+            json!({ "frame": { "id": "main-frame", "url": "https://a.test/" } }),
+            Some(&session.session_id),
+        );
+        for _ in 0..100 {
+            if manager.isolated_worlds.read().await.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        evaluate().await.unwrap();
+        assert_eq!(server.requests("Page.createIsolatedWorld").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn clearing_the_last_request_rule_stops_interception() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        let rule = RequestRule {
+            id: 1,
+            name: "Move docs".to_string(),
+            url_pattern: "*://old.test/*".to_string(),
+            resource_type: None,
+            action: RuleAction::Redirect {
+                url: "https://new.test/".to_string(),
+            },
+            enabled: true,
+        };
+
+        manager.set_request_rules(vec![rule.clone()]).await.unwrap();
+        let enabled = server.requests("Fetch.enable");
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(
+            enabled[0]["params"],
+            json!({
+                "handleAuthRequests": false,
+                "patterns": [{ "urlPattern": "*://old.test/*" }],
+            })
+        );
+
+        // Adding a rule widens the patterns without a second pump
+        let search = RequestRule {
+            id: 2,
+            url_pattern: "https://a.test/search?q=*".to_string(),
+            ..rule.clone()
+        };
+        manager.set_request_rules(vec![rule, search]).await.unwrap();
+        let enabled = server.requests("Fetch.enable");
+        assert_eq!(
+            enabled[1]["params"]["patterns"],
+            json!([
+                { "urlPattern": "*://old.test/*" },
+                { "urlPattern": "https://a.test/search\\?q=*" },
+            ])
+        );
+        assert_eq!(manager.fetch_pumps.read().await.len(), 1);
+
+        manager.set_request_rules(vec![]).await.unwrap();
+        let disabled = server.requests("Fetch.disable");
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(disabled[0]["sessionId"], session.session_id.as_str());
+        assert!(manager.fetch_pumps.read().await.is_empty());
+
+        // Nothing left to stop
+        manager.set_request_rules(vec![]).await.unwrap();
+        assert_eq!(server.requests("Fetch.disable").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn network_presets_throttle_the_network_and_cpu() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+
+        manager
+            .set_network_emulation(&tab.id, NetworkEmulation::Slow3G)
+            .await
+            .unwrap();
+
+        let network = server.requests("Network.emulateNetworkConditions");
+        assert_eq!(network.len(), 1);
+        assert_eq!(network[0]["sessionId"], session.session_id.as_str());
+        assert_eq!(
+            network[0]["params"],
+            json!({
+                "offline": false,
+                "latency": 2000.0,
+                "downloadThroughput": 50_000.0,
+                "uploadThroughput": 50_000.0,
+            })
+        );
+        let cpu = server.requests("Emulation.setCPUThrottlingRate");
+        assert_eq!(cpu[0]["params"]["rate"], 6.0);
+
+        manager
+            .set_network_emulation(&tab.id, NetworkEmulation::None)
+            .await
+            .unwrap();
+        let cpu = server.requests("Emulation.setCPUThrottlingRate");
+        assert_eq!(cpu[1]["params"]["rate"], 1.0);
+        assert_eq!(
+            manager.get_network_emulation(&tab.id).await,
+            NetworkEmulation::None
+        );
+    }
+
+    #[tokio::test]
+    async fn screencast_frames_dont_crowd_out_other_events() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        let mut events = session.subscribe();
+        let mut frames = session.subscribe_frames();
+
+        for _ in 0..300 {
+            server.emit(
+                "Page.screencastFrame",
+                json!({ "data": "" }),
+                Some(&session.session_id),
+            );
+        }
+        server.emit(
+            "Fetch.requestPaused",
+            json!({ "requestId": "r1" }),
+            Some(&session.session_id),
+        );
+
+        let paused = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(paused.method, "Fetch.requestPaused");
+        let frame = tokio::time::timeout(Duration::from_secs(1), recv_event(&mut frames))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.method, "Page.screencastFrame");
+    }
+
+    #[tokio::test]
+    async fn mouse_input_is_dispatched_to_the_tab() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let input: MouseInput = serde_json::from_value(json!({
+            "kind": "mousePressed",
+            "x": 10.0,
+            "y": 20.0,
+            "button": "left",
+            "click_count": 1,
+        }))
+        .unwrap();
+
+        manager.dispatch_mouse_event(&tab.id, &input).await.unwrap();
+
+        let dispatched = server.requests("Input.dispatchMouseEvent");
+        assert_eq!(dispatched.len(), 1);
+        assert_eq!(dispatched[0]["params"]["type"], "mousePressed");
+        assert_eq!(dispatched[0]["params"]["button"], "left");
+        assert_eq!(dispatched[0]["params"]["x"], 10.0);
+    }
+
+    #[tokio::test]
+    async fn file_inputs_are_resolved_by_selector() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        server.on("DOM.getDocument", |_| {
+            Ok(json!({ "root": { "nodeId": 1 } }))
+        });
+        server.on("DOM.querySelector", |params| {
+            let node_id = if params["selector"] == "#upload" {
+                7
+            } else {
+                0
+            };
+            Ok(json!({ "nodeId": node_id }))
+        });
+        let files = vec!["/tmp/report.pdf".to_string()];
+
+        manager
+            .set_file_input_files(&tab.id, "#upload", &files)
+            .await
+            .unwrap();
+        let set = server.requests("DOM.setFileInputFiles");
+        assert_eq!(set[0]["params"]["nodeId"], 7);
+        assert_eq!(set[0]["params"]["files"], json!(files));
+
+        assert!(matches!(
+            manager
+                .set_file_input_files(&tab.id, "#missing", &files)
+                .await,
+            Err(BrowserError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn re_enabling_file_chooser_interception_answers_once() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        let files = vec!["/tmp/report.pdf".to_string()];
+
+        let intercept = |files: Option<Vec<String>>| manager.intercept_file_chooser(&tab.id, files);
+        intercept(Some(files.clone())).await.unwrap();
+        intercept(None).await.unwrap();
+        intercept(Some(files.clone())).await.unwrap();
+
+        server.emit(
+            "Page.fileChooserOpened",
+            json!({ "backendNodeId": 3 }),
+            Some(&session.session_id),
+        );
+        let answer = server
+            .wait_for_request("DOM.setFileInputFiles")
+            .await
+            .unwrap();
+        assert_eq!(answer["params"]["files"], json!(files));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(server.requests("DOM.setFileInputFiles").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cdp_errors_surface_as_cdp_errors() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        server.on("Target.createTarget", |_| Err("Target crashed".to_string()));
+
+        match manager.session(&tab.id).await {
+            Err(BrowserError::Cdp(message)) => assert_eq!(message, "Target crashed"),
+            other => panic!(
+                "expected a CDP error, got {:?}",
+                other.map(|s| s.session_id)
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn events_reach_the_right_session() {
+        let (manager, server) = connected_manager().await;
+        let tab = manager.create_tab(None, None).await.unwrap();
+        let session = manager.session(&tab.id).await.unwrap();
+        let mut events = session.subscribe();
+
+        server.emit("Page.loadEventFired", json!({}), Some("some-other-session"));
+        server.emit(
+            "Page.loadEventFired",
+            json!({ "timestamp": 1.0 }),
+            Some(&session.session_id),
+        );
+
+        let event = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                let event = recv_event(&mut events).await.unwrap();
+                if event.is_for(&session) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(event.method, "Page.loadEventFired");
+        assert_eq!(event.params["timestamp"], 1.0);
+    }
 }
@@ -0,0 +1,253 @@
+use crate::error::{BrowserError, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, warn};
+
+type PendingMap = HashMap<u64, oneshot::Sender<Result<Value>>>;
+
+/// An event pushed by Chrome over the DevTools connection.
+#[derive(Debug, Clone)]
+pub struct CdpEvent {
+    pub method: String,
+    pub params: Value,
+    pub session_id: Option<String>,
+}
+
+impl CdpEvent {
+    pub fn is_for(&self, session: &CdpSession) -> bool {
+        self.session_id.as_deref() == Some(session.session_id.as_str())
+    }
+}
+
+/// Receives the next event, skipping over any the receiver fell behind on.
+///
+/// Returns `None` once the connection has closed.
+pub async fn recv_event(events: &mut broadcast::Receiver<CdpEvent>) -> Option<CdpEvent> {
+    loop {
+        match events.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("CDP event receiver skipped {} events", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// A single WebSocket connection to the browser-level DevTools endpoint.
+///
+/// Page targets are driven through flattened sessions, so every tab shares
+/// this connection and is addressed by its `sessionId`.
+pub struct CdpConnection {
+    next_id: AtomicU64,
+    outgoing: mpsc::UnboundedSender<Message>,
+    pending: Arc<Mutex<PendingMap>>,
+    events: broadcast::Sender<CdpEvent>,
+    /// `Page.screencastFrame` events, kept apart so a burst of frames can't push
+    /// other events out from under slower receivers.
+    frames: broadcast::Sender<CdpEvent>,
+}
+
+impl CdpConnection {
+    pub async fn connect(ws_url: &str) -> Result<Arc<Self>> {
+        let (stream, _) = connect_async(ws_url)
+            .await
+            .map_err(|e| BrowserError::Cdp(format!("Failed to connect to {}: {}", ws_url, e)))?;
+        let (mut sink, mut source) = stream.split();
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+        let pending: Arc<Mutex<PendingMap>> = Arc::new(Mutex::new(HashMap::new()));
+        let (events, _) = broadcast::channel(256);
+        // Chrome holds each tab's next frame until the last is acked, so a few suffice
+        let (frames, _) = broadcast::channel(16);
+
+        tokio::spawn(async move {
+            while let Some(message) = outgoing_rx.recv().await {
+                if let Err(e) = sink.send(message).await {
+                    warn!("CDP write failed: {}", e);
+                    break;
+                }
+            }
+        });
+
+        let reader_pending = pending.clone();
+        let reader_events = events.clone();
+        let reader_frames = frames.clone();
+        tokio::spawn(async move {
+            while let Some(message) = source.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Close(_)) | Err(_) => break,
+                    Ok(_) => continue,
+                };
+
+                let value: Value = match serde_json::from_str(&text) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        warn!("Ignoring malformed CDP message: {}", e);
+                        continue;
+                    }
+                };
+
+                if let Some(id) = value.get("id").and_then(Value::as_u64) {
+                    if let Some(tx) = reader_pending.lock().await.remove(&id) {
+                        let result = match value.get("error") {
+                            Some(error) => Err(BrowserError::Cdp(
+                                error
+                                    .get("message")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("Unknown CDP error")
+                                    .to_string(),
+                            )),
+                            None => Ok(value.get("result").cloned().unwrap_or(Value::Null)),
+                        };
+                        let _ = tx.send(result);
+                    }
+                } else if let Some(method) = value.get("method").and_then(Value::as_str) {
+                    let event = CdpEvent {
+                        method: method.to_string(),
+                        params: value.get("params").cloned().unwrap_or(Value::Null),
+                        session_id: value
+                            .get("sessionId")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    };
+                    let _ = if event.method == "Page.screencastFrame" {
+                        reader_frames.send(event)
+                    } else {
+                        reader_events.send(event)
+                    };
+                }
+            }
+
+            // Fail anything still waiting so callers don't hang forever
+            for (_, tx) in reader_pending.lock().await.drain() {
+                let _ = tx.send(Err(BrowserError::Cdp("Connection closed".to_string())));
+            }
+            debug!("CDP connection closed");
+        });
+
+        Ok(Arc::new(Self {
+            next_id: AtomicU64::new(1),
+            outgoing,
+            pending,
+            events,
+            frames,
+        }))
+    }
+
+    pub async fn send(
+        &self,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = json!({ "id": id, "method": method, "params": params });
+        if let Some(session_id) = session_id {
+            request["sessionId"] = json!(session_id);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+
+        if self
+            .outgoing
+            .send(Message::Text(request.to_string()))
+            .is_err()
+        {
+            self.pending.lock().await.remove(&id);
+            return Err(BrowserError::Cdp("Connection closed".to_string()));
+        }
+
+        rx.await
+            .map_err(|_| BrowserError::Cdp(format!("No response to {}", method)))?
+    }
+
+    /// Receives every event except screencast frames, which go to `subscribe_frames`.
+    pub fn subscribe(&self) -> broadcast::Receiver<CdpEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn subscribe_frames(&self) -> broadcast::Receiver<CdpEvent> {
+        self.frames.subscribe()
+    }
+}
+
+/// A flattened session attached to one page target.
+#[derive(Clone)]
+pub struct CdpSession {
+    conn: Arc<CdpConnection>,
+    pub session_id: String,
+    pub target_id: String,
+}
+
+impl CdpSession {
+    pub async fn attach(conn: Arc<CdpConnection>, target_id: &str) -> Result<Self> {
+        let result = conn
+            .send(
+                None,
+                "Target.attachToTarget",
+                json!({ "targetId": target_id, "flatten": true }),
+            )
+            .await?;
+
+        let session_id = result
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| BrowserError::Cdp("attachToTarget returned no sessionId".to_string()))?
+            .to_string();
+
+        Ok(Self {
+            conn,
+            session_id,
+            target_id: target_id.to_string(),
+        })
+    }
+
+    pub async fn send(&self, method: &str, params: Value) -> Result<Value> {
+        self.conn.send(Some(&self.session_id), method, params).await
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CdpEvent> {
+        self.conn.subscribe()
+    }
+
+    pub fn subscribe_frames(&self) -> broadcast::Receiver<CdpEvent> {
+        self.conn.subscribe_frames()
+    }
+}
+
+/// Polls `browser_ws_url` until Chrome answers or `timeout` passes.
+pub async fn wait_for_browser_ws_url(port: u16, timeout: Duration) -> Result<String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match browser_ws_url(port).await {
+            Ok(ws_url) => return Ok(ws_url),
+            Err(e) if Instant::now() >= deadline => return Err(e),
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+}
+
+/// Looks up the browser-level WebSocket URL from Chrome's HTTP endpoint.
+pub async fn browser_ws_url(port: u16) -> Result<String> {
+    let version: Value = reqwest::get(format!("http://127.0.0.1:{}/json/version", port))
+        .await
+        .map_err(|e| BrowserError::Network(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| BrowserError::Network(e.to_string()))?;
+
+    version
+        .get("webSocketDebuggerUrl")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| BrowserError::Cdp("Chrome did not report a WebSocket URL".to_string()))
+}
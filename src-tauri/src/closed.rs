@@ -0,0 +1,75 @@
+use crate::browser::{Tab, TabId};
+use crate::groups::GroupId;
+use crate::windows::WindowId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How many closed tabs are remembered for reopening.
+pub const MAX_CLOSED_TABS: usize = 25;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavigationEntry {
+    pub url: String,
+    pub title: String,
+}
+
+/// A tab as it was when closed, newest first in the recently closed list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosedTab {
+    /// The id the tab had while open. A reopened tab gets a new one.
+    pub id: TabId,
+    pub url: String,
+    pub title: String,
+    pub favicon: Option<String>,
+    pub window_id: WindowId,
+    pub position: i32,
+    pub group_id: Option<GroupId>,
+    pub pinned: bool,
+    /// Back/forward entries, oldest first.
+    pub history: Vec<NavigationEntry>,
+    /// Which of `history` the tab was showing.
+    pub history_index: usize,
+    pub closed_at: String,
+}
+
+impl ClosedTab {
+    pub fn new(tab: &Tab, history: Vec<NavigationEntry>, history_index: usize) -> Self {
+        Self {
+            id: tab.id.clone(),
+            url: tab.url.clone(),
+            title: tab.title.clone(),
+            favicon: tab.favicon.clone(),
+            window_id: tab.window_id.clone(),
+            position: tab.position,
+            group_id: tab.group_id.clone(),
+            pinned: tab.pinned,
+            history,
+            history_index,
+            closed_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// A reopened tab together with the back/forward entries it had when closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReopenedTab {
+    pub tab: Tab,
+    pub history: Vec<NavigationEntry>,
+    pub history_index: usize,
+}
+
+/// Reads the entries and current index from a `Page.getNavigationHistory` result.
+pub fn navigation_entries(result: &Value) -> (Vec<NavigationEntry>, usize) {
+    let entries: Vec<NavigationEntry> = result["entries"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|e| NavigationEntry {
+            url: e["url"].as_str().unwrap_or_default().to_string(),
+            title: e["title"].as_str().unwrap_or_default().to_string(),
+        })
+        .collect();
+    let index = result["currentIndex"].as_u64().unwrap_or_default() as usize;
+    let index = index.min(entries.len().saturating_sub(1));
+    (entries, index)
+}
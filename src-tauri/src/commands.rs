@@ -1,14 +1,31 @@
-use crate::browser::{get_browser_manager, Tab, TabId};
+use crate::browser::{get_browser_manager, validate_upload_paths, LifecycleSettings, Tab, TabId};
+use crate::closed::{ClosedTab, ReopenedTab};
 use crate::db::get_db;
-use crate::error::BrowserError;
+use crate::events::{emit, BrowserEvent};
+use crate::find::FindResult;
+use crate::groups::{GroupColor, GroupId, TabGroup, TabGroupUpdate, TabStrip};
+use crate::marks::{Mark, MarkedScreenshot};
+use crate::metrics::{ProcessInfo, TabMetrics};
+use crate::omnibox::Suggestion;
+use crate::popups::{popup_host, PopupPolicy, PopupRule};
+use crate::proxy::{ProxyConfig, ProxyCredentials};
+use crate::restore::NamedSession;
+use crate::rules::{RequestRule, RuleAction};
+use crate::screencast::{KeyInput, MouseInput, ScreencastSettings};
+use crate::script::{EvaluationResult, ScriptWorld};
+use crate::security::{CertificateDecision, CertificateError};
+use crate::sitedata::{SiteData, SiteDataType};
 use crate::storage::{
-    add_bookmark, add_history_entry, add_message_to_conversation, clear_history, create_conversation,
-    delete_bookmark, get_bookmarks, get_conversation, get_history, get_settings, update_settings,
-    Bookmark, Conversation, HistoryEntry, Settings,
+    add_history_entry, add_message_to_conversation, create_conversation, Bookmark, Conversation,
+    HistoryEntry, Settings,
 };
+use crate::throttling::NetworkEmulation;
+use crate::windows::{BrowserWindow, WindowId};
+use crate::zoom::{ZoomAction, ZoomLevel, DEFAULT_ZOOM};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, State};
-use tracing::{debug, error, info};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tracing::{debug, info};
 
 // Global state for browser manager
 pub struct BrowserState;
@@ -26,13 +43,639 @@ pub fn greet(name: &str) -> GreetingResponse {
 }
 
 #[tauri::command]
-pub async fn create_tab(url: Option<String>) -> Result<Tab, String> {
+pub async fn create_tab(
+    url: Option<String>,
+    browser_context_id: Option<String>,
+    window_id: Option<WindowId>,
+) -> Result<Tab, String> {
     debug!("Creating tab with URL: {:?}", url);
 
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        let result = match window_id {
+            Some(window_id) => {
+                manager
+                    .create_tab_in_window(&window_id, url, browser_context_id)
+                    .await
+            }
+            None => manager.create_tab(url, browser_context_id).await,
+        };
+        result.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn close_tab(tab_id: TabId) -> Result<(), String> {
+    debug!("Closing tab: {}", tab_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.close_tab(&tab_id).await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn switch_tab(tab_id: TabId) -> Result<Tab, String> {
+    debug!("Switching to tab: {}", tab_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.switch_tab(&tab_id).await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Moves a tab within the tab strip and returns all tabs in their new order.
+#[tauri::command]
+pub async fn move_tab(tab_id: TabId, new_index: usize) -> Result<Vec<Tab>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .move_tab(&tab_id, new_index)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_recently_closed_tabs() -> Result<Vec<ClosedTab>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        Ok(manager.get_recently_closed_tabs().await)
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Reopens the given recently closed tab, or the most recent one when `closed_id` is omitted.
+#[tauri::command]
+pub async fn reopen_closed_tab(closed_id: Option<TabId>) -> Result<ReopenedTab, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .reopen_closed_tab(closed_id.as_deref())
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn pin_tab(tab_id: TabId) -> Result<Tab, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .set_tab_pinned(&tab_id, true)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn unpin_tab(tab_id: TabId) -> Result<Tab, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .set_tab_pinned(&tab_id, false)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn create_tab_group(
+    tab_ids: Vec<TabId>,
+    title: Option<String>,
+    color: Option<GroupColor>,
+) -> Result<TabGroup, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .create_tab_group(
+                &tab_ids,
+                title.unwrap_or_default(),
+                color.unwrap_or_default(),
+            )
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Renames, recolors, collapses or expands a group; fields left out are unchanged.
+#[tauri::command]
+pub async fn update_tab_group(
+    group_id: GroupId,
+    update: TabGroupUpdate,
+) -> Result<TabGroup, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .update_tab_group(&group_id, update)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn ungroup_tabs(group_id: GroupId) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .ungroup_tabs(&group_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn move_tab_group(group_id: GroupId, new_index: usize) -> Result<Vec<Tab>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .move_tab_group(&group_id, new_index)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn navigate_tab(tab_id: TabId, url: String) -> Result<Tab, String> {
+    debug!("Navigating tab {} to URL: {}", tab_id, url);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .navigate_tab(&tab_id, &url)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Returns the tab strip of one window, or of every window when `window_id` is `None`.
+#[tauri::command]
+pub async fn get_tabs(window_id: Option<WindowId>) -> Result<TabStrip, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        let mut tabs = manager.get_tabs().await;
+        let mut groups = manager.get_tab_groups().await;
+        if let Some(window_id) = window_id {
+            tabs.retain(|t| t.window_id == window_id);
+            groups.retain(|g| tabs.iter().any(|t| t.group_id.as_ref() == Some(&g.id)));
+        }
+        Ok(TabStrip {
+            tabs,
+            groups,
+            windows: manager.get_windows().await,
+        })
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Returns all tabs, most recently used first.
+#[tauri::command]
+pub async fn get_recent_tabs() -> Result<Vec<Tab>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        Ok(manager.get_recent_tabs().await)
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_tab_metrics() -> Result<Vec<TabMetrics>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.get_tab_metrics().await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_process_info() -> Result<Vec<ProcessInfo>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.get_process_info().await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn kill_tab_process(tab_id: TabId) -> Result<(), String> {
+    debug!("Killing renderer for tab: {}", tab_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .kill_tab_process(&tab_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn find_in_page(
+    tab_id: TabId,
+    query: String,
+    case_sensitive: Option<bool>,
+) -> Result<FindResult, String> {
+    debug!("Finding \"{}\" in tab {}", query, tab_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .find_in_page(&tab_id, &query, case_sensitive.unwrap_or(false))
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn find_next(tab_id: TabId) -> Result<FindResult, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .find_step(&tab_id, true)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn find_previous(tab_id: TabId) -> Result<FindResult, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .find_step(&tab_id, false)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn stop_find(tab_id: TabId) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.clear_find(&tab_id).await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn evaluate_script(
+    tab_id: TabId,
+    expression: String,
+    world: Option<ScriptWorld>,
+) -> Result<EvaluationResult, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .evaluate(&tab_id, &expression, world.unwrap_or_default())
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn call_script_function(
+    tab_id: TabId,
+    function_declaration: String,
+    args: Option<Vec<serde_json::Value>>,
+    world: Option<ScriptWorld>,
+) -> Result<EvaluationResult, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .call_function(
+                &tab_id,
+                &function_declaration,
+                &args.unwrap_or_default(),
+                world.unwrap_or_default(),
+            )
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn start_screencast(
+    app_handle: AppHandle,
+    tab_id: TabId,
+    settings: Option<ScreencastSettings>,
+) -> Result<(), String> {
+    debug!("Starting screencast for tab: {}", tab_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .start_screencast(app_handle, &tab_id, settings.unwrap_or_default())
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn stop_screencast(tab_id: TabId) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .stop_screencast(&tab_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn dispatch_mouse_event(tab_id: TabId, input: MouseInput) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .dispatch_mouse_event(&tab_id, &input)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn dispatch_key_event(tab_id: TabId, input: KeyInput) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .dispatch_key_event(&tab_id, &input)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn set_network_emulation(
+    tab_id: TabId,
+    emulation: NetworkEmulation,
+) -> Result<(), String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .set_network_emulation(&tab_id, emulation)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_network_emulation(tab_id: TabId) -> Result<NetworkEmulation, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        Ok(manager.get_network_emulation(&tab_id).await)
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn toggle_offline(tab_id: TabId) -> Result<bool, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .toggle_offline(&tab_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+async fn zoom(
+    app_handle: AppHandle,
+    tab_id: TabId,
+    action: ZoomAction,
+) -> Result<ZoomLevel, String> {
+    let zoom = if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .zoom_tab(&tab_id, action)
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        return Err("Browser manager not initialized".to_string());
+    };
+
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    let saved = if zoom.level == DEFAULT_ZOOM {
+        crate::storage::delete_zoom_level(&db, &zoom.origin).await
+    } else {
+        crate::storage::set_zoom_level(&db, &zoom.origin, zoom.level).await
+    };
+    saved.map_err(|e| e.to_string())?;
+
+    Ok(zoom)
+}
+
+#[tauri::command]
+pub async fn zoom_in(app_handle: AppHandle, tab_id: TabId) -> Result<ZoomLevel, String> {
+    zoom(app_handle, tab_id, ZoomAction::In).await
+}
+
+#[tauri::command]
+pub async fn zoom_out(app_handle: AppHandle, tab_id: TabId) -> Result<ZoomLevel, String> {
+    zoom(app_handle, tab_id, ZoomAction::Out).await
+}
+
+#[tauri::command]
+pub async fn reset_zoom(app_handle: AppHandle, tab_id: TabId) -> Result<ZoomLevel, String> {
+    zoom(app_handle, tab_id, ZoomAction::Reset).await
+}
+
+/// Proceeds past the certificate error a tab is stopped on and records the decision.
+#[tauri::command]
+pub async fn proceed_certificate_error(
+    app_handle: AppHandle,
+    tab_id: TabId,
+) -> Result<CertificateError, String> {
+    let error = if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .proceed_certificate_error(&tab_id)
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        return Err("Browser manager not initialized".to_string());
+    };
+
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    crate::storage::record_certificate_decision(&db, &error)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(error)
+}
+
+#[tauri::command]
+pub async fn get_certificate_decisions(
+    app_handle: AppHandle,
+) -> Result<Vec<CertificateDecision>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_certificate_decisions(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Screenshots a tab with numbered boxes over its interactive elements.
+#[tauri::command]
+pub async fn capture_marked_screenshot(tab_id: TabId) -> Result<MarkedScreenshot, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .capture_marked_screenshot(&tab_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn resolve_mark(tab_id: TabId, number: u32) -> Result<Mark, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .resolve_mark(&tab_id, number)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn click_mark(tab_id: TabId, number: u32) -> Result<Mark, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .click_mark(&tab_id, number)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_site_data() -> Result<Vec<SiteData>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager.get_site_data().await.map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Clears a site's stored data. Leaving `types` out clears everything.
+#[tauri::command]
+pub async fn clear_site_data(
+    site: String,
+    types: Option<Vec<SiteDataType>>,
+) -> Result<String, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .clear_site_data(&site, &types.unwrap_or_default())
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_zoom_levels(app_handle: AppHandle) -> Result<Vec<ZoomLevel>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_zoom_levels(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Opens a private browser context. Tabs created in it share cookies and storage
+/// with each other but not with the default profile.
+#[tauri::command]
+pub async fn create_browser_context(
+    proxy: Option<ProxyConfig>,
+    credentials: Option<ProxyCredentials>,
+) -> Result<String, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .create_browser_context(proxy, credentials)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn close_browser_context(browser_context_id: String) -> Result<(), String> {
     if let Some(manager) = get_browser_manager() {
         let manager = manager.lock().await;
         manager
-            .create_tab(url)
+            .close_browser_context(&browser_context_id)
             .await
             .map_err(|e| e.to_string())
     } else {
@@ -40,26 +683,341 @@ pub async fn create_tab(url: Option<String>) -> Result<Tab, String> {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentMessageRequest {
+    pub conversation_id: Option<String>,
+    pub tab_id: Option<String>,
+    pub message: String,
+    pub provider: String,
+    pub model: String,
+    /// Conditions to put the tab under before the agent acts on it, so test runs can
+    /// pin a slow or offline page.
+    #[serde(default)]
+    pub network_emulation: Option<NetworkEmulation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentMessageResponse {
+    pub conversation_id: String,
+    pub response: String,
+}
+
+#[tauri::command]
+pub async fn send_agent_message(
+    app_handle: AppHandle,
+    request: AgentMessageRequest,
+) -> Result<AgentMessageResponse, String> {
+    info!("Sending agent message with provider: {}", request.provider);
+
+    if let (Some(emulation), Some(tab_id)) = (request.network_emulation, &request.tab_id) {
+        set_network_emulation(tab_id.clone(), emulation).await?;
+    }
+
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    // Get or create conversation
+    let conversation_id = if let Some(id) = &request.conversation_id {
+        id.clone()
+    } else {
+        let conv = create_conversation(&db, request.tab_id.clone(), request.provider.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        conv.id
+    };
+
+    // Add user message to conversation
+    add_message_to_conversation(&db, &conversation_id, "user", &request.message)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // TODO: Implement actual AI provider integration
+    // For now, return a mock response
+    let mock_response = format!(
+        "I received your message: '{}' (Provider: {}, Model: {}). \
+         Full AI integration coming soon!",
+        request.message, request.provider, request.model
+    );
+
+    // Add assistant response to conversation
+    add_message_to_conversation(&db, &conversation_id, "assistant", &mock_response)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(AgentMessageResponse {
+        conversation_id,
+        response: mock_response,
+    })
+}
+
+#[tauri::command]
+pub async fn get_conversation(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<Option<Conversation>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_conversation(&db, &conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_settings(app_handle: AppHandle, settings: Settings) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::update_settings(&db, &settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::omnibox::set_search_engine(&settings.default_search_engine);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .set_lifecycle_settings(LifecycleSettings::from(&settings))
+            .await;
+        manager.set_proxy_config(settings.proxy.clone()).await;
+        manager
+            .set_activation_policy(settings.tab_close_activation)
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Saves or, with `None`, forgets the login for the default profile's proxy.
+#[tauri::command]
+pub async fn set_proxy_credentials(
+    app_handle: AppHandle,
+    credentials: Option<ProxyCredentials>,
+) -> Result<(), String> {
+    let credentials = credentials.filter(|c| !c.username.is_empty());
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::set_proxy_credentials(&db, credentials.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(manager) = get_browser_manager() {
+        manager
+            .lock()
+            .await
+            .set_proxy_credentials(credentials)
+            .await;
+    }
+
+    Ok(())
+}
+
+/// The saved proxy username, so settings can show who's signed in. The password is
+/// never sent back.
+#[tauri::command]
+pub async fn get_proxy_username(app_handle: AppHandle) -> Result<Option<String>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_proxy_credentials(&db)
+        .await
+        .map(|credentials| credentials.map(|c| c.username))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_settings(app_handle: AppHandle) -> Result<Settings, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_settings(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn record_history(
+    app_handle: AppHandle,
+    url: String,
+    title: Option<String>,
+) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    add_history_entry(&db, &url, title.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::omnibox::record_visit(&url, title.as_deref());
+
+    emit(BrowserEvent::HistoryChanged);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_history(app_handle: AppHandle) -> Result<Vec<HistoryEntry>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_history(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_history(app_handle: AppHandle) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::clear_history(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::omnibox::clear_history_index();
+
+    emit(BrowserEvent::HistoryChanged);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_bookmark(
+    app_handle: AppHandle,
+    url: String,
+    title: String,
+    folder_id: Option<i64>,
+) -> Result<Bookmark, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let bookmark = crate::storage::add_bookmark(&db, &url, &title, folder_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    emit(BrowserEvent::BookmarksChanged);
+    Ok(bookmark)
+}
+
 #[tauri::command]
-pub async fn close_tab(tab_id: TabId) -> Result<(), String> {
-    debug!("Closing tab: {}", tab_id);
+pub async fn get_bookmarks(app_handle: AppHandle) -> Result<Vec<Bookmark>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
 
-    if let Some(manager) = get_browser_manager() {
-        let manager = manager.lock().await;
-        manager.close_tab(&tab_id).await.map_err(|e| e.to_string())
-    } else {
-        Err("Browser manager not initialized".to_string())
-    }
+    crate::storage::get_bookmarks(&db)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn switch_tab(tab_id: TabId) -> Result<Tab, String> {
-    debug!("Switching to tab: {}", tab_id);
+pub async fn delete_bookmark(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::delete_bookmark(&db, id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    emit(BrowserEvent::BookmarksChanged);
+    Ok(())
+}
+
+/// Asks the user to pick a folder to allow uploads from and returns it, or `None` if
+/// they cancel. Paths are only approved through this dialog, never taken from the caller.
+#[tauri::command]
+pub async fn approve_upload_path(app_handle: AppHandle) -> Result<Option<String>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .set_title("Allow uploads from this folder")
+        .pick_folder(move |folder| {
+            let _ = tx.send(folder);
+        });
+    let Some(folder) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = folder
+        .into_path()
+        .map_err(|e| e.to_string())?
+        .to_string_lossy()
+        .into_owned();
+
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::add_upload_permission(&db, &path)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(path))
+}
+
+#[tauri::command]
+pub async fn get_approved_upload_paths(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::get_upload_permissions(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revoke_upload_path(app_handle: AppHandle, path: String) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    crate::storage::remove_upload_permission(&db, &path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn approved_upload_files(
+    app_handle: &AppHandle,
+    files: &[String],
+) -> Result<Vec<String>, String> {
+    let db = get_db(app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let approved = crate::storage::get_upload_permissions(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    validate_upload_paths(files, &approved).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_file_input_files(
+    app_handle: AppHandle,
+    tab_id: TabId,
+    selector: String,
+    files: Vec<String>,
+) -> Result<(), String> {
+    debug!(
+        "Setting {} file(s) on {} in tab {}",
+        files.len(),
+        selector,
+        tab_id
+    );
+
+    let files = approved_upload_files(&app_handle, &files).await?;
 
     if let Some(manager) = get_browser_manager() {
         let manager = manager.lock().await;
         manager
-            .switch_tab(&tab_id)
+            .set_file_input_files(&tab_id, &selector, &files)
             .await
             .map_err(|e| e.to_string())
     } else {
@@ -68,13 +1026,20 @@ pub async fn switch_tab(tab_id: TabId) -> Result<Tab, String> {
 }
 
 #[tauri::command]
-pub async fn navigate_tab(tab_id: TabId, url: String) -> Result<Tab, String> {
-    debug!("Navigating tab {} to URL: {}", tab_id, url);
+pub async fn intercept_file_chooser(
+    app_handle: AppHandle,
+    tab_id: TabId,
+    files: Option<Vec<String>>,
+) -> Result<(), String> {
+    let files = match files {
+        Some(files) => Some(approved_upload_files(&app_handle, &files).await?),
+        None => None,
+    };
 
     if let Some(manager) = get_browser_manager() {
         let manager = manager.lock().await;
         manager
-            .navigate_tab(&tab_id, &url)
+            .intercept_file_chooser(&tab_id, files)
             .await
             .map_err(|e| e.to_string())
     } else {
@@ -82,192 +1047,283 @@ pub async fn navigate_tab(tab_id: TabId, url: String) -> Result<Tab, String> {
     }
 }
 
-#[tauri::command]
-pub async fn get_tabs() -> Result<Vec<Tab>, String> {
+async fn reload_popup_rules(db: &crate::db::Database) -> Result<(), String> {
+    let rules = crate::storage::get_popup_rules(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
     if let Some(manager) = get_browser_manager() {
         let manager = manager.lock().await;
-        Ok(manager.get_tabs().await)
-    } else {
-        Err("Browser manager not initialized".to_string())
+        manager.set_popup_rules(rules).await;
     }
+    Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AgentMessageRequest {
-    pub conversation_id: Option<String>,
-    pub tab_id: Option<String>,
-    pub message: String,
-    pub provider: String,
-    pub model: String,
-}
+#[tauri::command]
+pub async fn get_popup_rules(app_handle: AppHandle) -> Result<Vec<PopupRule>, String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AgentMessageResponse {
-    pub conversation_id: String,
-    pub response: String,
+    crate::storage::get_popup_rules(&db)
+        .await
+        .map_err(|e| e.to_string())
 }
 
+/// Sets the popup policy for a site. `site` may be a bare host or any URL on it.
 #[tauri::command]
-pub async fn send_agent_message(
+pub async fn set_popup_rule(
     app_handle: AppHandle,
-    request: AgentMessageRequest,
-) -> Result<AgentMessageResponse, String> {
-    info!(
-        "Sending agent message with provider: {}",
-        request.provider
-    );
+    site: String,
+    policy: PopupPolicy,
+) -> Result<(), String> {
+    let host = popup_host(&site)
+        .or_else(|| popup_host(&format!("https://{}", site)))
+        .ok_or_else(|| format!("Invalid site: {}", site))?;
 
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    // Get or create conversation
-    let conversation_id = if let Some(id) = &request.conversation_id {
-        id.clone()
-    } else {
-        let conv = create_conversation(&db, request.tab_id.clone(), request.provider.clone())
-            .await
-            .map_err(|e| e.to_string())?;
-        conv.id
-    };
-
-    // Add user message to conversation
-    add_message_to_conversation(&db, &conversation_id, "user", &request.message)
+    crate::storage::set_popup_rule(&db, &host, policy)
         .await
         .map_err(|e| e.to_string())?;
+    reload_popup_rules(&db).await
+}
 
-    // TODO: Implement actual AI provider integration
-    // For now, return a mock response
-    let mock_response = format!(
-        "I received your message: '{}' (Provider: {}, Model: {}). \
-         Full AI integration coming soon!",
-        request.message, request.provider, request.model
-    );
+#[tauri::command]
+pub async fn delete_popup_rule(app_handle: AppHandle, host: String) -> Result<(), String> {
+    let db = get_db(&app_handle)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
 
-    // Add assistant response to conversation
-    add_message_to_conversation(&db, &conversation_id, "assistant", &mock_response)
+    crate::storage::delete_popup_rule(&db, &host)
         .await
         .map_err(|e| e.to_string())?;
+    reload_popup_rules(&db).await
+}
 
-    Ok(AgentMessageResponse {
-        conversation_id,
-        response: mock_response,
-    })
+async fn reload_request_rules(db: &crate::db::Database) -> Result<(), String> {
+    let rules = crate::storage::get_request_rules(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .set_request_rules(rules)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_conversation(
-    app_handle: AppHandle,
-    conversation_id: String,
-) -> Result<Option<Conversation>, String> {
+pub async fn get_request_rules(app_handle: AppHandle) -> Result<Vec<RequestRule>, String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::get_conversation(&db, &conversation_id)
+    crate::storage::get_request_rules(&db)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn update_settings(
+pub async fn add_request_rule(
     app_handle: AppHandle,
-    settings: Settings,
-) -> Result<(), String> {
+    name: String,
+    url_pattern: String,
+    resource_type: Option<String>,
+    action: RuleAction,
+) -> Result<RequestRule, String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::update_settings(&db, &settings)
-        .await
-        .map_err(|e| e.to_string())
+    let rule = crate::storage::add_request_rule(
+        &db,
+        &name,
+        &url_pattern,
+        resource_type.as_deref(),
+        &action,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    reload_request_rules(&db).await?;
+    Ok(rule)
 }
 
 #[tauri::command]
-pub async fn get_settings(app_handle: AppHandle) -> Result<Settings, String> {
+pub async fn set_request_rule_enabled(
+    app_handle: AppHandle,
+    id: i64,
+    enabled: bool,
+) -> Result<(), String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::get_settings(&db)
+    crate::storage::set_request_rule_enabled(&db, id, enabled)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    reload_request_rules(&db).await
 }
 
 #[tauri::command]
-pub async fn record_history(
-    app_handle: AppHandle,
-    url: String,
-    title: Option<String>,
-) -> Result<(), String> {
+pub async fn delete_request_rule(app_handle: AppHandle, id: i64) -> Result<(), String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    add_history_entry(&db, &url, title.as_deref())
+    crate::storage::delete_request_rule(&db, id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    reload_request_rules(&db).await
 }
 
+/// Saves the open tabs under `name`, overwriting a session with the same name.
 #[tauri::command]
-pub async fn get_history(app_handle: AppHandle) -> Result<Vec<HistoryEntry>, String> {
+pub async fn save_named_session(
+    app_handle: AppHandle,
+    name: String,
+) -> Result<NamedSession, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Session name can't be empty".to_string());
+    }
+
+    let tabs: Vec<Tab> = if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .get_tabs()
+            .await
+            .into_iter()
+            .filter(|t| t.browser_context_id.is_none())
+            .collect()
+    } else {
+        return Err("Browser manager not initialized".to_string());
+    };
+
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::get_history(&db)
+    crate::storage::save_named_session(&db, &name, &tabs)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn clear_history(app_handle: AppHandle) -> Result<(), String> {
+pub async fn get_named_sessions(app_handle: AppHandle) -> Result<Vec<NamedSession>, String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::clear_history(&db)
+    crate::storage::get_named_sessions(&db)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Reopens a saved session, either alongside the open tabs or in place of them.
 #[tauri::command]
-pub async fn add_bookmark(
+pub async fn open_named_session(
     app_handle: AppHandle,
-    url: String,
-    title: String,
-    folder_id: Option<i64>,
-) -> Result<Bookmark, String> {
+    session_id: i64,
+    replace: bool,
+) -> Result<Vec<Tab>, String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::add_bookmark(&db, &url, &title, folder_id)
+    let tabs = crate::storage::get_named_session_tabs(&db, session_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .open_tab_set(tabs, replace)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
 }
 
 #[tauri::command]
-pub async fn get_bookmarks(app_handle: AppHandle) -> Result<Vec<Bookmark>, String> {
+pub async fn delete_named_session(app_handle: AppHandle, session_id: i64) -> Result<(), String> {
     let db = get_db(&app_handle)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    crate::storage::get_bookmarks(&db)
+    crate::storage::delete_named_session(&db, session_id)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_bookmark(
-    app_handle: AppHandle,
-    id: i64,
-) -> Result<(), String> {
-    let db = get_db(&app_handle)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+pub async fn get_windows() -> Result<Vec<BrowserWindow>, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        Ok(manager.get_windows().await)
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
 
-    crate::storage::delete_bookmark(&db, id)
-        .await
-        .map_err(|e| e.to_string())
+/// Opens a new window, moving `tab_id` into it if given.
+#[tauri::command]
+pub async fn create_window(tab_id: Option<TabId>) -> Result<BrowserWindow, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .create_window(tab_id.as_deref())
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn close_window(window_id: WindowId) -> Result<(), String> {
+    debug!("Closing window: {}", window_id);
+
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .close_window(&window_id)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Moves a tab to `index` in another window's tab strip, or to its end.
+#[tauri::command]
+pub async fn move_tab_to_window(
+    tab_id: TabId,
+    window_id: WindowId,
+    index: Option<usize>,
+) -> Result<Tab, String> {
+    if let Some(manager) = get_browser_manager() {
+        let manager = manager.lock().await;
+        manager
+            .move_tab_to_window(&tab_id, &window_id, index)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Browser manager not initialized".to_string())
+    }
+}
+
+/// Suggests open tabs, bookmarks and history pages matching what was typed in the
+/// address bar, ending with a search for it.
+#[tauri::command]
+pub async fn get_omnibox_suggestions(query: String) -> Result<Vec<Suggestion>, String> {
+    let tabs = crate::browser::open_tabs().await;
+    Ok(crate::omnibox::get_suggestions(&query, &tabs))
 }
@@ -1,7 +1,7 @@
 use crate::error::Result;
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
 use std::path::PathBuf;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
 pub type Database = Pool<Sqlite>;
 
@@ -29,6 +29,9 @@ pub async fn init_database(app_handle: &AppHandle) -> Result<Database> {
             favicon TEXT,
             position INTEGER NOT NULL DEFAULT 0,
             is_active BOOLEAN NOT NULL DEFAULT 0,
+            pinned BOOLEAN NOT NULL DEFAULT 0,
+            group_id TEXT,
+            window_id TEXT NOT NULL DEFAULT 'main',
             created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
         );
@@ -37,6 +40,34 @@ pub async fn init_database(app_handle: &AppHandle) -> Result<Database> {
     .execute(&pool)
     .await?;
 
+    add_column_if_missing(&pool, "tabs", "pinned", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(&pool, "tabs", "group_id", "TEXT").await?;
+    add_column_if_missing(&pool, "tabs", "window_id", "TEXT NOT NULL DEFAULT 'main'").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tab_groups (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL DEFAULT '',
+            color TEXT NOT NULL DEFAULT 'grey',
+            collapsed BOOLEAN NOT NULL DEFAULT 0
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS windows (
+            id TEXT PRIMARY KEY,
+            position INTEGER NOT NULL DEFAULT 0
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS history (
@@ -94,6 +125,113 @@ pub async fn init_database(app_handle: &AppHandle) -> Result<Database> {
     .execute(&pool)
     .await?;
 
+    // One row at most. Older versions kept these inside the proxy setting, so move
+    // them out of it.
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS proxy_credentials (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            username TEXT NOT NULL,
+            password TEXT NOT NULL
+        );
+        INSERT OR IGNORE INTO proxy_credentials (id, username, password)
+            SELECT 1, json_extract(value, '$.username'), COALESCE(json_extract(value, '$.password'), '')
+            FROM settings
+            WHERE key = 'proxy' AND COALESCE(json_extract(value, '$.username'), '') != '';
+        UPDATE settings SET value = json_remove(value, '$.username', '$.password')
+            WHERE key = 'proxy';
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS upload_permissions (
+            path TEXT PRIMARY KEY,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS popup_policies (
+            host TEXT PRIMARY KEY,
+            policy TEXT NOT NULL DEFAULT 'allow'
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS request_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            url_pattern TEXT NOT NULL,
+            resource_type TEXT,
+            action TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS zoom_levels (
+            origin TEXT PRIMARY KEY,
+            level REAL NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS certificate_decisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            host TEXT NOT NULL,
+            url TEXT NOT NULL,
+            error_type TEXT NOT NULL,
+            decided_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS named_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS named_session_tabs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            title TEXT NOT NULL DEFAULT 'New Tab',
+            favicon TEXT,
+            position INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (session_id) REFERENCES named_sessions(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_named_session_tabs_session ON named_session_tabs(session_id);
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
     sqlx::query(
         r#"
         INSERT OR IGNORE INTO settings (key, value) VALUES
@@ -101,7 +239,12 @@ pub async fn init_database(app_handle: &AppHandle) -> Result<Database> {
         ('default_search_engine', 'google'),
         ('ai_provider', 'openai'),
         ('ai_model', 'gpt-4o'),
-        ('agent_mode_enabled', 'false');
+        ('agent_mode_enabled', 'false'),
+        ('tab_lifecycle_enabled', 'true'),
+        ('tab_freeze_after_minutes', '5'),
+        ('tab_discard_after_minutes', '30'),
+        ('restore_session', 'true'),
+        ('tab_close_activation', 'recent');
         "#,
     )
     .execute(&pool)
@@ -111,15 +254,39 @@ pub async fn init_database(app_handle: &AppHandle) -> Result<Database> {
     Ok(pool)
 }
 
+/// Adds a column to a table created by an older version, where `CREATE TABLE IF NOT
+/// EXISTS` left the old schema in place.
+async fn add_column_if_missing(
+    pool: &Database,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+    let exists = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == column);
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
 fn get_app_dir(app_handle: &AppHandle) -> Result<PathBuf> {
-    let path = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| {
-            crate::error::BrowserError::Configuration(
-                format!("Failed to get app data directory: {}", e)
-            )
-        })?;
+    let path = app_handle.path().app_data_dir().map_err(|e| {
+        crate::error::BrowserError::Configuration(format!(
+            "Failed to get app data directory: {}",
+            e
+        ))
+    })?;
     Ok(path)
 }
 
@@ -7,6 +7,7 @@ pub enum BrowserError {
     Network(String),
     Configuration(String),
     NotFound(String),
+    PermissionDenied(String),
 }
 
 impl fmt::Display for BrowserError {
@@ -17,6 +18,7 @@ impl fmt::Display for BrowserError {
             BrowserError::Network(msg) => write!(f, "Network error: {}", msg),
             BrowserError::Configuration(msg) => write!(f, "Configuration error: {}", msg),
             BrowserError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            BrowserError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
         }
     }
 }
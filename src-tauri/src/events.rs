@@ -0,0 +1,314 @@
+use crate::browser::{Tab, TabId};
+use crate::cdp::{recv_event, CdpEvent};
+use crate::groups::{GroupId, TabGroup};
+use crate::security::CertificateError;
+use crate::windows::WindowId;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Name of the single Tauri event every backend notification is sent on.
+pub const BROWSER_EVENT: &str = "browser-event";
+
+/// Bumped whenever a payload changes shape so the frontend can detect a mismatch.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// A state change pushed from the backend to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BrowserEvent {
+    TabCreated {
+        tab: Tab,
+    },
+    TabUpdated {
+        tab: Tab,
+    },
+    TabClosed {
+        tab_id: TabId,
+    },
+    TabActivated {
+        tab_id: TabId,
+    },
+    /// A window's tab strip order changed; `tab_ids` lists its tabs in their new order.
+    TabsReordered {
+        window_id: WindowId,
+        tab_ids: Vec<TabId>,
+    },
+    /// A group was created, renamed, recolored, collapsed or expanded.
+    TabGroupUpdated {
+        group: TabGroup,
+    },
+    TabGroupRemoved {
+        group_id: GroupId,
+    },
+    /// A window was opened; the Tauri window for it follows.
+    WindowCreated {
+        window_id: WindowId,
+    },
+    WindowClosed {
+        window_id: WindowId,
+    },
+    Navigated {
+        tab_id: TabId,
+        url: String,
+    },
+    /// A navigation was stopped by a certificate error; show the interstitial.
+    CertificateError {
+        tab_id: TabId,
+        error: CertificateError,
+    },
+    DownloadStarted {
+        guid: String,
+        url: String,
+        suggested_filename: String,
+    },
+    DownloadProgress {
+        guid: String,
+        received_bytes: u64,
+        total_bytes: u64,
+        /// `"inProgress"`, `"completed"` or `"canceled"`.
+        state: String,
+    },
+    HistoryChanged,
+    /// The recently closed tabs list changed.
+    ClosedTabsChanged,
+    BookmarksChanged,
+}
+
+impl BrowserEvent {
+    /// Whether the event changes anything that is saved with the open tabs.
+    pub fn changes_tabs(&self) -> bool {
+        matches!(
+            self,
+            BrowserEvent::TabCreated { .. }
+                | BrowserEvent::TabUpdated { .. }
+                | BrowserEvent::TabClosed { .. }
+                | BrowserEvent::TabActivated { .. }
+                | BrowserEvent::TabsReordered { .. }
+                | BrowserEvent::TabGroupUpdated { .. }
+                | BrowserEvent::TabGroupRemoved { .. }
+                | BrowserEvent::WindowCreated { .. }
+                | BrowserEvent::WindowClosed { .. }
+                | BrowserEvent::Navigated { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EventEnvelope {
+    version: u32,
+    timestamp: String,
+    #[serde(flatten)]
+    event: BrowserEvent,
+}
+
+impl EventEnvelope {
+    fn new(event: BrowserEvent) -> Self {
+        Self {
+            version: EVENT_SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event,
+        }
+    }
+}
+
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+/// Every emitted event is also published here for backend listeners.
+static LOCAL_EVENTS: Lazy<broadcast::Sender<BrowserEvent>> =
+    Lazy::new(|| broadcast::channel(256).0);
+
+pub fn init_events(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+pub fn subscribe() -> broadcast::Receiver<BrowserEvent> {
+    LOCAL_EVENTS.subscribe()
+}
+
+/// Sends an event to every window and backend subscriber. Windows only get it once
+/// `init_events` has run.
+pub fn emit(event: BrowserEvent) {
+    let _ = LOCAL_EVENTS.send(event.clone());
+
+    let Some(app_handle) = APP_HANDLE.get() else {
+        return;
+    };
+
+    if let Err(e) = app_handle.emit(BROWSER_EVENT, EventEnvelope::new(event)) {
+        warn!("Failed to emit browser event: {}", e);
+    }
+}
+
+/// Turns browser-level download events into `Download*` notifications.
+pub async fn watch_downloads(mut events: broadcast::Receiver<CdpEvent>) {
+    while let Some(event) = recv_event(&mut events).await {
+        let params = &event.params;
+        match event.method.as_str() {
+            "Browser.downloadWillBegin" => emit(BrowserEvent::DownloadStarted {
+                guid: params["guid"].as_str().unwrap_or_default().to_string(),
+                url: params["url"].as_str().unwrap_or_default().to_string(),
+                suggested_filename: params["suggestedFilename"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+            "Browser.downloadProgress" => emit(BrowserEvent::DownloadProgress {
+                guid: params["guid"].as_str().unwrap_or_default().to_string(),
+                received_bytes: params["receivedBytes"].as_f64().unwrap_or_default() as u64,
+                total_bytes: params["totalBytes"].as_f64().unwrap_or_default() as u64,
+                state: params["state"].as_str().unwrap_or_default().to_string(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::LifecycleState;
+    use crate::groups::GroupColor;
+    use crate::security::TabSecurity;
+    use serde_json::json;
+
+    fn tab() -> Tab {
+        Tab {
+            id: "t1".to_string(),
+            url: "https://a.test/".to_string(),
+            title: String::new(),
+            favicon: None,
+            window_id: "main".to_string(),
+            position: 0,
+            is_active: true,
+            lifecycle_state: LifecycleState::Active,
+            opener_id: None,
+            browser_context_id: None,
+            group_id: None,
+            pinned: false,
+            security: TabSecurity::default(),
+        }
+    }
+
+    /// One of every event, with whether it should cause the open tabs to be saved.
+    ///
+    /// The match has no catch-all, so a new event can't be added without deciding.
+    fn every_event() -> Vec<(BrowserEvent, bool)> {
+        let events = vec![
+            BrowserEvent::TabCreated { tab: tab() },
+            BrowserEvent::TabUpdated { tab: tab() },
+            BrowserEvent::TabClosed {
+                tab_id: "t1".to_string(),
+            },
+            BrowserEvent::TabActivated {
+                tab_id: "t1".to_string(),
+            },
+            BrowserEvent::TabsReordered {
+                window_id: "main".to_string(),
+                tab_ids: vec!["t1".to_string()],
+            },
+            BrowserEvent::TabGroupUpdated {
+                group: TabGroup {
+                    id: "g1".to_string(),
+                    title: "Work".to_string(),
+                    color: GroupColor::Blue,
+                    collapsed: false,
+                },
+            },
+            BrowserEvent::TabGroupRemoved {
+                group_id: "g1".to_string(),
+            },
+            BrowserEvent::WindowCreated {
+                window_id: "w2".to_string(),
+            },
+            BrowserEvent::WindowClosed {
+                window_id: "w2".to_string(),
+            },
+            BrowserEvent::Navigated {
+                tab_id: "t1".to_string(),
+                url: "https://b.test/".to_string(),
+            },
+            BrowserEvent::CertificateError {
+                tab_id: "t1".to_string(),
+                error: CertificateError {
+                    url: "https://expired.test/".to_string(),
+                    host: "expired.test".to_string(),
+                    error_type: "net::ERR_CERT_DATE_INVALID".to_string(),
+                },
+            },
+            BrowserEvent::DownloadStarted {
+                guid: "d1".to_string(),
+                url: "https://a.test/file.zip".to_string(),
+                suggested_filename: "file.zip".to_string(),
+            },
+            BrowserEvent::DownloadProgress {
+                guid: "d1".to_string(),
+                received_bytes: 1,
+                total_bytes: 2,
+                state: "inProgress".to_string(),
+            },
+            BrowserEvent::HistoryChanged,
+            BrowserEvent::ClosedTabsChanged,
+            BrowserEvent::BookmarksChanged,
+        ];
+
+        events
+            .into_iter()
+            .map(|event| {
+                let saved = match &event {
+                    BrowserEvent::TabCreated { .. }
+                    | BrowserEvent::TabUpdated { .. }
+                    | BrowserEvent::TabClosed { .. }
+                    | BrowserEvent::TabActivated { .. }
+                    | BrowserEvent::TabsReordered { .. }
+                    | BrowserEvent::TabGroupUpdated { .. }
+                    | BrowserEvent::TabGroupRemoved { .. }
+                    | BrowserEvent::WindowCreated { .. }
+                    | BrowserEvent::WindowClosed { .. }
+                    | BrowserEvent::Navigated { .. } => true,
+                    BrowserEvent::CertificateError { .. }
+                    | BrowserEvent::DownloadStarted { .. }
+                    | BrowserEvent::DownloadProgress { .. }
+                    | BrowserEvent::HistoryChanged
+                    | BrowserEvent::ClosedTabsChanged
+                    | BrowserEvent::BookmarksChanged => false,
+                };
+                (event, saved)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_events_that_change_saved_tabs_trigger_persistence() {
+        for (event, saved) in every_event() {
+            assert_eq!(event.changes_tabs(), saved, "{:?}", event);
+        }
+    }
+
+    #[test]
+    fn emitted_events_carry_the_schema_version() {
+        let before = chrono::Utc::now();
+        let envelope = serde_json::to_value(EventEnvelope::new(BrowserEvent::TabClosed {
+            tab_id: "t1".to_string(),
+        }))
+        .unwrap();
+
+        assert_eq!(envelope["version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(envelope["type"], "tab_closed");
+        assert_eq!(envelope["tab_id"], "t1");
+        let timestamp = envelope["timestamp"].as_str().unwrap();
+        let timestamp = chrono::DateTime::parse_from_rfc3339(timestamp).unwrap();
+        assert!(timestamp >= before);
+
+        // Unit variants are flattened alongside the envelope too
+        let envelope =
+            serde_json::to_value(EventEnvelope::new(BrowserEvent::HistoryChanged)).unwrap();
+        assert_eq!(
+            envelope.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["timestamp", "type", "version"]
+        );
+        assert_eq!(envelope["type"], json!("history_changed"));
+    }
+}
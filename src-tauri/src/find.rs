@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+/// Installs `window.__aetherFind` in the current world and leaves it in place
+/// for later `activate`/`clear` calls.
+///
+/// Matches are painted with the CSS Custom Highlight API so the page's DOM is
+/// left alone apart from a single `<style>` element.
+pub const FIND_SCRIPT: &str = r#"
+window.__aetherFind = window.__aetherFind || (() => {
+  const ALL = 'aether-find';
+  const CURRENT = 'aether-find-current';
+  const STYLE_ID = '__aether-find-style';
+  let ranges = [];
+
+  const ensureStyle = () => {
+    if (document.getElementById(STYLE_ID)) return;
+    const style = document.createElement('style');
+    style.id = STYLE_ID;
+    style.textContent =
+      `::highlight(${ALL}) { background-color: #fde68a; color: inherit; }` +
+      `::highlight(${CURRENT}) { background-color: #f59e0b; color: inherit; }`;
+    (document.head || document.documentElement).appendChild(style);
+  };
+
+  const clear = () => {
+    ranges = [];
+    CSS.highlights.delete(ALL);
+    CSS.highlights.delete(CURRENT);
+    document.getElementById(STYLE_ID)?.remove();
+  };
+
+  const search = (query, caseSensitive) => {
+    clear();
+    if (!query || !document.body) return 0;
+
+    const needle = caseSensitive ? query : query.toLowerCase();
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+      acceptNode: (node) => {
+        const parent = node.parentElement;
+        if (!parent || ['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE'].includes(parent.tagName)) {
+          return NodeFilter.FILTER_REJECT;
+        }
+        return parent.getClientRects().length ? NodeFilter.FILTER_ACCEPT : NodeFilter.FILTER_REJECT;
+      },
+    });
+
+    for (let node = walker.nextNode(); node; node = walker.nextNode()) {
+      const text = caseSensitive ? node.data : node.data.toLowerCase();
+      for (let i = text.indexOf(needle); i !== -1; i = text.indexOf(needle, i + needle.length)) {
+        const range = new Range();
+        range.setStart(node, i);
+        range.setEnd(node, i + needle.length);
+        ranges.push(range);
+      }
+    }
+
+    if (ranges.length) {
+      ensureStyle();
+      CSS.highlights.set(ALL, new Highlight(...ranges));
+    }
+    return ranges.length;
+  };
+
+  const activate = (index) => {
+    CSS.highlights.delete(CURRENT);
+    const range = ranges[index];
+    if (!range) return false;
+    CSS.highlights.set(CURRENT, new Highlight(range));
+    range.startContainer.parentElement?.scrollIntoView({ block: 'center', inline: 'nearest' });
+    return true;
+  };
+
+  return { search, activate, clear };
+})();
+"#;
+
+/// Matches found in one frame's isolated world.
+#[derive(Debug, Clone)]
+pub struct FrameMatches {
+    pub context_id: i64,
+    pub count: usize,
+}
+
+/// An in-progress find for one tab.
+#[derive(Debug, Clone)]
+pub struct FindSession {
+    pub query: String,
+    pub case_sensitive: bool,
+    pub frames: Vec<FrameMatches>,
+    pub current: Option<usize>,
+}
+
+impl FindSession {
+    pub fn match_count(&self) -> usize {
+        self.frames.iter().map(|f| f.count).sum()
+    }
+
+    /// Maps a page-wide match index to the frame holding it and its index there.
+    pub fn locate(&self, index: usize) -> Option<(i64, usize)> {
+        let mut remaining = index;
+        for frame in &self.frames {
+            if remaining < frame.count {
+                return Some((frame.context_id, remaining));
+            }
+            remaining -= frame.count;
+        }
+        None
+    }
+
+    /// Index of the next match in the given direction, wrapping at either end.
+    pub fn step(&self, forward: bool) -> Option<usize> {
+        let total = self.match_count();
+        if total == 0 {
+            return None;
+        }
+
+        Some(match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => total - 1,
+            (Some(i), true) => (i + 1) % total,
+            (Some(i), false) => (i + total - 1) % total,
+        })
+    }
+
+    pub fn result(&self) -> FindResult {
+        FindResult {
+            query: self.query.clone(),
+            match_count: self.match_count(),
+            current_index: self.current,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindResult {
+    pub query: String,
+    pub match_count: usize,
+    pub current_index: Option<usize>,
+}
@@ -0,0 +1,156 @@
+use crate::browser::{Tab, TabId};
+use crate::windows::BrowserWindow;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub type GroupId = String;
+
+/// The group colors Chrome offers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupColor {
+    #[default]
+    Grey,
+    Blue,
+    Red,
+    Yellow,
+    Green,
+    Pink,
+    Purple,
+    Cyan,
+    Orange,
+}
+
+impl GroupColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupColor::Grey => "grey",
+            GroupColor::Blue => "blue",
+            GroupColor::Red => "red",
+            GroupColor::Yellow => "yellow",
+            GroupColor::Green => "green",
+            GroupColor::Pink => "pink",
+            GroupColor::Purple => "purple",
+            GroupColor::Cyan => "cyan",
+            GroupColor::Orange => "orange",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "blue" => GroupColor::Blue,
+            "red" => GroupColor::Red,
+            "yellow" => GroupColor::Yellow,
+            "green" => GroupColor::Green,
+            "pink" => GroupColor::Pink,
+            "purple" => GroupColor::Purple,
+            "cyan" => GroupColor::Cyan,
+            "orange" => GroupColor::Orange,
+            _ => GroupColor::Grey,
+        }
+    }
+}
+
+/// A named run of adjacent tabs. Its tabs point at it through `Tab::group_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabGroup {
+    pub id: GroupId,
+    pub title: String,
+    pub color: GroupColor,
+    /// Collapsed groups show only their header in the tab strip.
+    pub collapsed: bool,
+}
+
+/// Changes to a group; fields left out stay as they are.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TabGroupUpdate {
+    pub title: Option<String>,
+    pub color: Option<GroupColor>,
+    pub collapsed: Option<bool>,
+}
+
+impl TabGroup {
+    pub fn apply(&mut self, update: TabGroupUpdate) {
+        if let Some(title) = update.title {
+            self.title = title;
+        }
+        if let Some(color) = update.color {
+            self.color = color;
+        }
+        if let Some(collapsed) = update.collapsed {
+            self.collapsed = collapsed;
+        }
+    }
+}
+
+/// Everything the tab strip shows, in strip order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabStrip {
+    pub tabs: Vec<Tab>,
+    pub groups: Vec<TabGroup>,
+    /// Every open window, the main window first.
+    pub windows: Vec<BrowserWindow>,
+}
+
+/// Pushes `index` past the end of any group it would split, so groups stay in one piece.
+pub fn index_outside_groups(
+    tabs: &HashMap<TabId, Tab>,
+    order: &[TabId],
+    mut index: usize,
+) -> usize {
+    let group_at = |i: usize| tabs.get(&order[i]).and_then(|t| t.group_id.as_ref());
+    while index > 0 && index < order.len() {
+        let before = group_at(index - 1);
+        if before.is_none() || before != group_at(index) {
+            break;
+        }
+        index += 1;
+    }
+    index
+}
+
+/// The group a tab dropped at `index` of `order` belongs to, judged by its new neighbours.
+///
+/// Dropped between two tabs of one group, it joins that group. Otherwise it keeps its
+/// own group only if it is still next to another member, or is the group's only tab.
+pub fn group_after_move(
+    tabs: &HashMap<TabId, Tab>,
+    order: &[TabId],
+    index: usize,
+) -> Option<GroupId> {
+    let group_at = |i: Option<usize>| {
+        i.and_then(|i| order.get(i))
+            .and_then(|id| tabs.get(id))
+            .and_then(|t| t.group_id.clone())
+    };
+    let before = group_at(index.checked_sub(1));
+    let after = group_at(Some(index + 1));
+    if before.is_some() && before == after {
+        return before;
+    }
+
+    let own = group_at(Some(index));
+    own.filter(|g| {
+        before.as_ref() == Some(g)
+            || after.as_ref() == Some(g)
+            || tabs
+                .values()
+                .all(|t| t.id == order[index] || t.group_id.as_ref() != Some(g))
+    })
+}
+
+/// Drops groups no tab points at any more and returns their ids.
+pub fn remove_empty_groups(
+    tabs: &HashMap<TabId, Tab>,
+    groups: &mut HashMap<GroupId, TabGroup>,
+) -> Vec<GroupId> {
+    let empty: Vec<GroupId> = groups
+        .keys()
+        .filter(|id| !tabs.values().any(|t| t.group_id.as_ref() == Some(*id)))
+        .cloned()
+        .collect();
+    for id in &empty {
+        groups.remove(id);
+    }
+    empty
+}
@@ -0,0 +1,113 @@
+use crate::cdp::{recv_event, CdpEvent, CdpSession};
+use crate::proxy::ProxyCredentials;
+use crate::rules::{resolve_paused_request, RequestRule};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, warn};
+
+/// `Fetch.enable` parameters for a session that rewrites requests or answers proxy auth.
+///
+/// Only requests an enabled rule's URL glob could match are paused. A proxy that needs
+/// credentials can challenge any request, so with credentials every request is paused
+/// and auth challenges are handled too.
+pub fn fetch_enable_params(rules: &[RequestRule], handle_auth: bool) -> serde_json::Value {
+    if handle_auth {
+        return json!({
+            "handleAuthRequests": true,
+            "patterns": [{ "urlPattern": "*" }],
+        });
+    }
+
+    let mut patterns: Vec<String> = rules
+        .iter()
+        .filter(|r| r.enabled)
+        .map(|r| fetch_url_pattern(&r.url_pattern))
+        .collect();
+    patterns.sort();
+    patterns.dedup();
+    json!({
+        "handleAuthRequests": false,
+        "patterns": patterns
+            .into_iter()
+            .map(|p| json!({ "urlPattern": p }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Turns a rule's glob into a Fetch URL pattern, where `?` is also a wildcard and
+/// backslash escapes.
+fn fetch_url_pattern(glob: &str) -> String {
+    glob.replace('\\', "\\\\").replace('?', "\\?")
+}
+
+/// Answers paused requests and auth challenges for one session until it goes away.
+///
+/// With the Fetch domain enabled every request matching its patterns stops at
+/// `Fetch.requestPaused` and must be explicitly continued, rewritten by a matching
+/// rule, or fulfilled.
+pub async fn pump_fetch(
+    session: CdpSession,
+    credentials: Option<ProxyCredentials>,
+    rules: Arc<RwLock<Vec<RequestRule>>>,
+    mut events: broadcast::Receiver<CdpEvent>,
+) {
+    // Requests we've already offered credentials for; a second challenge means
+    // they were rejected, so cancel rather than loop forever
+    let mut answered = HashSet::new();
+
+    while let Some(event) = recv_event(&mut events).await {
+        if !event.is_for(&session) {
+            continue;
+        }
+
+        if event.method == "Fetch.requestPaused" {
+            let session = session.clone();
+            let rules = rules.clone();
+            tokio::spawn(async move {
+                // A snapshot, so the lock isn't held while a rule reads its file
+                let rules = rules.read().await.clone();
+                let (method, params) = resolve_paused_request(&rules, &event.params).await;
+                if let Err(e) = session.send(method, params).await {
+                    debug!("{} failed: {}", method, e);
+                }
+            });
+            continue;
+        }
+
+        let request_id = event.params["requestId"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let (method, params) = match event.method.as_str() {
+            "Fetch.authRequired" => {
+                let from_proxy = event.params["authChallenge"]["source"] == "Proxy";
+                let response = match &credentials {
+                    Some(c) if from_proxy && answered.insert(request_id.clone()) => json!({
+                        "response": "ProvideCredentials",
+                        "username": c.username,
+                        "password": c.password,
+                    }),
+                    Some(_) if from_proxy => {
+                        warn!("Proxy rejected the configured credentials");
+                        json!({ "response": "CancelAuth" })
+                    }
+                    _ => json!({ "response": "Default" }),
+                };
+                (
+                    "Fetch.continueWithAuth",
+                    json!({ "requestId": request_id, "authChallengeResponse": response }),
+                )
+            }
+            _ => continue,
+        };
+
+        let session = session.clone();
+        tokio::spawn(async move {
+            if let Err(e) = session.send(method, params).await {
+                debug!("{} failed: {}", method, e);
+            }
+        });
+    }
+}
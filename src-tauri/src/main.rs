@@ -1,13 +1,36 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod activation;
 mod browser;
+mod cdp;
+mod closed;
 mod commands;
 mod db;
 mod error;
+mod events;
+mod find;
+mod groups;
+mod intercept;
+mod marks;
+mod metrics;
+mod omnibox;
+mod popups;
+mod proxy;
+mod restore;
+mod rules;
+mod screencast;
+mod script;
+mod security;
+mod sitedata;
 mod storage;
+#[cfg(test)]
+mod test_support;
+mod throttling;
+mod windows;
+mod zoom;
 
-use browser::init_browser_manager;
+use browser::{init_browser_manager, start_lifecycle_manager, LifecycleSettings};
 use tauri::Manager;
 use tracing::info;
 
@@ -24,19 +47,85 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             info!("Aether starting up...");
+            events::init_events(app.handle().clone());
+            windows::start_window_sync(app.handle().clone());
 
             // Initialize database
             let app_handle = app.handle();
             tauri::async_runtime::block_on(async {
-                if let Err(e) = db::init_database(&app_handle).await {
-                    tracing::error!("Failed to initialize database: {}", e);
+                match db::init_database(&app_handle).await {
+                    Ok(db) => {
+                        let settings = storage::get_settings(&db).await.unwrap_or_default();
+                        let popup_rules = storage::get_popup_rules(&db).await.unwrap_or_default();
+                        let proxy_credentials = storage::get_proxy_credentials(&db)
+                            .await
+                            .unwrap_or_default();
+                        let request_rules =
+                            storage::get_request_rules(&db).await.unwrap_or_default();
+                        let zoom_levels = storage::get_zoom_levels(&db).await.unwrap_or_default();
+                        omnibox::load_history_index(
+                            storage::get_all_history(&db).await.unwrap_or_default(),
+                        );
+                        omnibox::load_bookmarks(
+                            storage::get_bookmarks(&db).await.unwrap_or_default(),
+                        );
+                        omnibox::set_search_engine(&settings.default_search_engine);
+                        // Pinned tabs come back even when the rest of the session doesn't
+                        let saved_tabs: Vec<_> = storage::get_saved_tabs(&db)
+                            .await
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter(|t| settings.restore_session || t.pinned)
+                            .collect();
+                        let (saved_groups, saved_windows) = if settings.restore_session {
+                            (
+                                storage::get_saved_tab_groups(&db).await.unwrap_or_default(),
+                                storage::get_saved_windows(&db).await.unwrap_or_default(),
+                            )
+                        } else {
+                            (Vec::new(), Vec::new())
+                        };
+                        if let Some(manager) = browser::get_browser_manager() {
+                            let manager = manager.lock().await;
+                            manager
+                                .set_lifecycle_settings(LifecycleSettings::from(&settings))
+                                .await;
+                            manager.set_popup_rules(popup_rules).await;
+                            manager.set_proxy_config(settings.proxy.clone()).await;
+                            manager.set_proxy_credentials(proxy_credentials).await;
+                            manager
+                                .set_activation_policy(settings.tab_close_activation)
+                                .await;
+                            manager.set_zoom_levels(zoom_levels).await;
+                            manager
+                                .restore_tabs(saved_tabs, saved_groups, saved_windows)
+                                .await;
+                            if let Err(e) = manager.set_request_rules(request_rules).await {
+                                tracing::error!("Failed to apply request rules: {}", e);
+                            }
+                        }
+                        omnibox::start_bookmark_sync(db.clone());
+                        restore::start_tab_persistence(db);
+                    }
+                    Err(e) => tracing::error!("Failed to initialize database: {}", e),
                 }
             });
 
+            // Launched after the settings above so Chrome starts with the saved proxy
+            let chrome_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = browser::start_chrome(chrome_handle).await {
+                    tracing::error!("Failed to start Chrome: {}", e);
+                }
+            });
+            start_lifecycle_manager();
+
             Ok(())
         })
+        .on_window_event(windows::handle_window_event)
         .invoke_handler(tauri::generate_handler![
             commands::greet,
             commands::create_tab,
@@ -48,12 +137,74 @@ fn main() {
             commands::get_conversation,
             commands::update_settings,
             commands::get_settings,
+            commands::set_proxy_credentials,
+            commands::get_proxy_username,
             commands::record_history,
             commands::get_history,
             commands::clear_history,
             commands::add_bookmark,
             commands::get_bookmarks,
             commands::delete_bookmark,
+            commands::approve_upload_path,
+            commands::get_approved_upload_paths,
+            commands::revoke_upload_path,
+            commands::set_file_input_files,
+            commands::intercept_file_chooser,
+            commands::get_tab_metrics,
+            commands::get_process_info,
+            commands::kill_tab_process,
+            commands::find_in_page,
+            commands::find_next,
+            commands::find_previous,
+            commands::stop_find,
+            commands::evaluate_script,
+            commands::call_script_function,
+            commands::start_screencast,
+            commands::stop_screencast,
+            commands::dispatch_mouse_event,
+            commands::dispatch_key_event,
+            commands::get_popup_rules,
+            commands::set_popup_rule,
+            commands::delete_popup_rule,
+            commands::create_browser_context,
+            commands::close_browser_context,
+            commands::get_request_rules,
+            commands::add_request_rule,
+            commands::set_request_rule_enabled,
+            commands::delete_request_rule,
+            commands::set_network_emulation,
+            commands::get_network_emulation,
+            commands::toggle_offline,
+            commands::zoom_in,
+            commands::zoom_out,
+            commands::reset_zoom,
+            commands::get_zoom_levels,
+            commands::get_site_data,
+            commands::clear_site_data,
+            commands::proceed_certificate_error,
+            commands::get_certificate_decisions,
+            commands::capture_marked_screenshot,
+            commands::resolve_mark,
+            commands::click_mark,
+            commands::save_named_session,
+            commands::get_named_sessions,
+            commands::open_named_session,
+            commands::delete_named_session,
+            commands::move_tab,
+            commands::create_tab_group,
+            commands::update_tab_group,
+            commands::ungroup_tabs,
+            commands::move_tab_group,
+            commands::pin_tab,
+            commands::unpin_tab,
+            commands::get_recently_closed_tabs,
+            commands::reopen_closed_tab,
+            commands::get_recent_tabs,
+            commands::get_windows,
+            commands::create_window,
+            commands::close_window,
+            commands::move_tab_to_window,
+            commands::get_omnibox_suggestions,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
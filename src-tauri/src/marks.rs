@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Elements a user (or a model) can act on.
+pub const MARKABLE_SELECTOR: &str = "a[href], button, input:not([type=hidden]), select, textarea, \
+     summary, [role=button], [role=link], [role=checkbox], [role=radio], [role=tab], \
+     [role=menuitem], [role=option], [role=switch], [contenteditable=''], \
+     [contenteditable=true], [tabindex]:not([tabindex='-1'])";
+
+/// Past this many marks the labels crowd each other out and stop helping the model.
+pub const MAX_MARKS: usize = 200;
+
+/// A numbered box drawn over an interactive element.
+///
+/// The box is in CSS pixels relative to the viewport; multiply by the device pixel
+/// ratio to get screenshot pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mark {
+    pub number: u32,
+    /// Stable DevTools reference to the element, valid until the page navigates.
+    pub backend_node_id: i64,
+    pub tag: String,
+    /// Accessible hint from `aria-label`, `placeholder`, `title`, `alt` or `name`.
+    pub label: Option<String>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Mark {
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedScreenshot {
+    /// Base64-encoded PNG of the viewport with the marks drawn on.
+    pub image: String,
+    pub marks: Vec<Mark>,
+}
+
+/// Bounding box `(x, y, width, height)` of a `DOM.getBoxModel` quad.
+pub fn quad_bounds(quad: &Value) -> Option<(f64, f64, f64, f64)> {
+    let points: Vec<f64> = quad.as_array()?.iter().filter_map(Value::as_f64).collect();
+    if points.len() != 8 {
+        return None;
+    }
+
+    let xs = [points[0], points[2], points[4], points[6]];
+    let ys = [points[1], points[3], points[5], points[7]];
+    let min_x = xs.iter().copied().fold(f64::INFINITY, f64::min);
+    let max_x = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let min_y = ys.iter().copied().fold(f64::INFINITY, f64::min);
+    let max_y = ys.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    Some((min_x, min_y, max_x - min_x, max_y - min_y))
+}
+
+/// Picks a short label from the flat `[name, value, ...]` attribute list of `DOM.describeNode`.
+pub fn element_label(attributes: &Value) -> Option<String> {
+    let attributes: Vec<&str> = attributes
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+
+    ["aria-label", "placeholder", "title", "alt", "name"]
+        .iter()
+        .find_map(|wanted| {
+            attributes
+                .chunks(2)
+                .find(|pair| pair.len() == 2 && pair[0] == *wanted && !pair[1].trim().is_empty())
+                .map(|pair| pair[1].trim().to_string())
+        })
+}
+
+/// Script that draws the marks in a fixed overlay above the page.
+pub fn overlay_expression(marks: &[Mark]) -> String {
+    let boxes: Vec<Value> = marks
+        .iter()
+        .map(|m| serde_json::json!([m.number, m.x, m.y, m.width, m.height]))
+        .collect();
+
+    format!(
+        r##"(() => {{
+  document.getElementById("__aether_marks")?.remove();
+  const root = document.createElement("div");
+  root.id = "__aether_marks";
+  root.style.cssText = "position:fixed;inset:0;pointer-events:none;z-index:2147483647";
+  const colors = ["#e11d48", "#2563eb", "#16a34a", "#d97706", "#7c3aed", "#0891b2"];
+  for (const [n, x, y, w, h] of {boxes}) {{
+    const color = colors[n % colors.length];
+    const box = document.createElement("div");
+    box.style.cssText = `position:absolute;left:${{x}}px;top:${{y}}px;width:${{w}}px;height:${{h}}px;outline:2px solid ${{color}};box-sizing:border-box`;
+    const label = document.createElement("span");
+    label.textContent = n;
+    label.style.cssText = `position:absolute;left:-2px;top:-2px;transform:translateY(-100%);background:${{color}};color:#fff;font:bold 11px/1.2 sans-serif;padding:1px 3px;border-radius:2px`;
+    if (y < 16) label.style.transform = "none";
+    box.appendChild(label);
+    root.appendChild(box);
+  }}
+  document.documentElement.appendChild(root);
+}})()"##,
+        boxes = Value::Array(boxes)
+    )
+}
+
+pub const REMOVE_OVERLAY: &str = r#"document.getElementById("__aether_marks")?.remove()"#;
@@ -0,0 +1,111 @@
+use crate::browser::TabId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Resource usage for one tab, as reported by `Performance.getMetrics`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TabMetrics {
+    pub tab_id: TabId,
+    pub js_heap_used_size: u64,
+    pub js_heap_total_size: u64,
+    pub dom_nodes: u64,
+    pub documents: u64,
+    pub frames: u64,
+    pub js_event_listeners: u64,
+    pub layout_count: u64,
+    pub recalc_style_count: u64,
+    /// Total main-thread task time in seconds.
+    pub cpu_time: f64,
+    pub script_duration: f64,
+    pub layout_duration: f64,
+}
+
+impl TabMetrics {
+    pub fn from_performance_metrics(tab_id: &str, result: &Value) -> Self {
+        let mut metrics = TabMetrics {
+            tab_id: tab_id.to_string(),
+            ..Default::default()
+        };
+
+        let entries = result["metrics"].as_array().cloned().unwrap_or_default();
+        for entry in entries {
+            let value = entry["value"].as_f64().unwrap_or_default();
+            match entry["name"].as_str().unwrap_or_default() {
+                "JSHeapUsedSize" => metrics.js_heap_used_size = value as u64,
+                "JSHeapTotalSize" => metrics.js_heap_total_size = value as u64,
+                "Nodes" => metrics.dom_nodes = value as u64,
+                "Documents" => metrics.documents = value as u64,
+                "Frames" => metrics.frames = value as u64,
+                "JSEventListeners" => metrics.js_event_listeners = value as u64,
+                "LayoutCount" => metrics.layout_count = value as u64,
+                "RecalcStyleCount" => metrics.recalc_style_count = value as u64,
+                "TaskDuration" => metrics.cpu_time = value,
+                "ScriptDuration" => metrics.script_duration = value,
+                "LayoutDuration" => metrics.layout_duration = value,
+                _ => {}
+            }
+        }
+
+        metrics
+    }
+}
+
+/// A Chrome process from `SystemInfo.getProcessInfo`, with the tabs it renders.
+///
+/// Targets don't carry a pid, so `get_process_info` reads each tab's renderer from a
+/// short trace on its session and joins them here. Chrome may share one renderer
+/// between same-site tabs, so a process can list several.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub id: u32,
+    pub process_type: String,
+    /// Cumulative CPU time in seconds.
+    pub cpu_time: f64,
+    pub tab_ids: Vec<TabId>,
+    /// JS heap in use by the tabs this process renders, in bytes. Only known for
+    /// renderers hosting a tab.
+    pub memory: Option<u64>,
+}
+
+impl ProcessInfo {
+    pub fn list_from(result: &Value) -> Vec<Self> {
+        result["processInfo"]
+            .as_array()
+            .map(|processes| {
+                processes
+                    .iter()
+                    .map(|p| ProcessInfo {
+                        id: p["id"].as_u64().unwrap_or_default() as u32,
+                        process_type: p["type"].as_str().unwrap_or_default().to_string(),
+                        cpu_time: p["cpuTime"].as_f64().unwrap_or_default(),
+                        tab_ids: Vec::new(),
+                        memory: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Records that this process renders a tab using `metrics`.
+    pub fn add_tab(&mut self, metrics: &TabMetrics) {
+        self.tab_ids.push(metrics.tab_id.clone());
+        *self.memory.get_or_insert(0) += metrics.js_heap_used_size;
+    }
+}
+
+/// Finds the pid of the renderer hosting a page's main frame in the trace events
+/// Chrome reports when tracing starts on that page.
+pub fn renderer_pid_from_trace(events: &[Value]) -> Option<u32> {
+    events
+        .iter()
+        .filter(|e| e["name"] == "TracingStartedInBrowser")
+        .flat_map(|e| {
+            e["args"]["data"]["frames"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .find(|frame| frame.get("parent").is_none())
+        .and_then(|frame| frame["processId"].as_u64())
+        .map(|pid| pid as u32)
+}
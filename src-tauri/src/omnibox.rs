@@ -0,0 +1,804 @@
+use crate::browser::{Tab, TabId};
+use crate::db::Database;
+use crate::events::{subscribe, BrowserEvent};
+use crate::storage::{Bookmark, HistoryEntry};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+/// How many suggestions the omnibox shows, the search fallback included.
+pub const MAX_SUGGESTIONS: usize = 8;
+
+const TAB_BONUS: f64 = 20.0;
+const BOOKMARK_BONUS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    /// An open tab to switch to rather than navigate.
+    Tab,
+    Bookmark,
+    History,
+    /// Searching for the query with the default search engine.
+    Search,
+}
+
+/// A matched span in a suggestion's title or URL, in characters, `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub url: String,
+    pub title: String,
+    /// Set for `SuggestionKind::Tab`.
+    pub tab_id: Option<TabId>,
+    pub title_matches: Vec<MatchRange>,
+    pub url_matches: Vec<MatchRange>,
+    pub score: f64,
+}
+
+/// Lowercases `text` one character for one, so offsets in the result hold for the original.
+fn fold(text: &str) -> String {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// A bit per character present, to rule out most texts before matching. Letters and
+/// digits get a bit each; everything else shares the rest.
+fn char_mask(folded: &str) -> u64 {
+    folded
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .fold(0, |mask, c| {
+            let bit = match c {
+                'a'..='z' => c as u32 - 'a' as u32,
+                '0'..='9' => 26 + c as u32 - '0' as u32,
+                _ => 36 + c as u32 % 28,
+            };
+            mask | 1 << bit
+        })
+}
+
+fn first_char_mask(folded: &str) -> u64 {
+    folded
+        .chars()
+        .next()
+        .map_or(0, |c| char_mask(c.encode_utf8(&mut [0; 4])))
+}
+
+/// A title or URL prepared for matching.
+#[derive(Debug, Clone)]
+struct Text {
+    original: String,
+    folded: String,
+    mask: u64,
+    /// Where the host begins, past the scheme and any `www.`.
+    host_start: Option<usize>,
+    /// The characters at the start and at `host_start`, where the best matches begin.
+    anchors: u64,
+}
+
+impl Text {
+    fn new(text: &str) -> Self {
+        let folded = fold(text);
+        let host_start = folded.find("://").map(|i| i + 3).map(|i| {
+            if folded[i..].starts_with("www.") {
+                i + 4
+            } else {
+                i
+            }
+        });
+        let anchors = [0]
+            .into_iter()
+            .chain(host_start)
+            .map(|i| first_char_mask(&folded[i..]))
+            .fold(0, |mask, bit| mask | bit);
+        Self {
+            original: text.to_string(),
+            mask: char_mask(&folded),
+            folded,
+            host_start,
+            anchors,
+        }
+    }
+}
+
+struct Token {
+    folded: String,
+    chars: usize,
+    mask: u64,
+    first: u64,
+}
+
+impl Token {
+    /// Whether every character of the token appears somewhere in `text`.
+    fn may_match(&self, text: &Text) -> bool {
+        self.mask & !text.mask == 0
+    }
+}
+
+fn tokens(query: &str) -> Vec<Token> {
+    fold(query)
+        .split_whitespace()
+        .map(|t| Token {
+            folded: t.to_string(),
+            chars: t.chars().count(),
+            mask: char_mask(t),
+            first: first_char_mask(t),
+        })
+        .collect()
+}
+
+/// Where a token matched, as byte offsets into the folded text.
+enum Hit {
+    Substring(usize, usize),
+    Fuzzy(Vec<(usize, usize)>),
+}
+
+/// Finds `token` in `text` as it is and rates the match: 1 at the start of the text or
+/// of its host, 0.8 at a word boundary and 0.6 mid-word. Matches rated below `min` are
+/// not looked for.
+fn substring_match(token: &Token, text: &Text, min: f64) -> Option<(f64, usize)> {
+    let folded = text.folded.as_bytes();
+    let needle = token.folded.as_bytes();
+    if min > 0.8 {
+        if token.first & text.anchors == 0 {
+            return None;
+        }
+        return [0]
+            .into_iter()
+            .chain(text.host_start)
+            .find(|&i| folded[i..].starts_with(needle))
+            .map(|start| (1.0, start));
+    }
+    // A quick check first, as most texts that get this far still don't contain the token
+    if !text.folded.contains(token.folded.as_str()) {
+        return None;
+    }
+    // The first byte of a character never occurs inside another, so every hit is a boundary
+    let starts = folded
+        .iter()
+        .enumerate()
+        .filter(|&(i, &b)| b == needle[0] && folded[i..].starts_with(needle))
+        .map(|(i, _)| i);
+
+    let mut best: Option<(f64, usize)> = None;
+    for start in starts {
+        let quality = if start == 0 || Some(start) == text.host_start {
+            1.0
+        } else if text.folded[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_alphanumeric())
+        {
+            0.8
+        } else {
+            0.6
+        };
+        if !matches!(best, Some((q, _)) if q >= quality) {
+            best = Some((quality, start));
+        }
+        if quality == 1.0 {
+            break;
+        }
+    }
+    best.filter(|&(quality, _)| quality >= min)
+}
+
+/// Finds the token's characters in order in `text`, rated below any substring match
+/// by how closely they sit together.
+fn fuzzy_match(token: &Token, text: &Text) -> Option<(f64, Vec<(usize, usize)>)> {
+    // Single characters only match as they are; spread out they match nearly anything
+    if token.chars < 2 {
+        return None;
+    }
+    let folded = &text.folded;
+    let spans = if token.folded.is_ascii() {
+        // ASCII bytes never occur inside a multi-byte character, so bytes will do
+        subsequence_spans(token.folded.bytes(), folded.bytes().enumerate(), |_| 1)?
+    } else {
+        subsequence_spans(token.folded.chars(), folded.char_indices(), char::len_utf8)?
+    };
+
+    let (first, last) = (spans[0].0, spans[spans.len() - 1].1);
+    let span_chars = folded[first..last].chars().count();
+    let compactness = token.chars as f64 / span_chars as f64;
+    (compactness >= 0.25).then_some((0.4 * compactness, spans))
+}
+
+/// The best `fuzzy_match` can rate a token that isn't in the text as it is, which spans
+/// at least one more character.
+fn best_fuzzy(token: &Token) -> f64 {
+    if token.chars < 2 {
+        return 0.0;
+    }
+    0.4 * token.chars as f64 / (token.chars + 1) as f64
+}
+
+fn match_token(token: &Token, text: &Text) -> Option<Hit> {
+    if !token.may_match(text) {
+        return None;
+    }
+    if let Some((_, start)) = substring_match(token, text, 0.0) {
+        return Some(Hit::Substring(start, start + token.folded.len()));
+    }
+    fuzzy_match(token, text).map(|(_, spans)| Hit::Fuzzy(spans))
+}
+
+/// Byte spans of the first occurrence, left to right, of each of `wanted` in `text`,
+/// merging neighbours, or `None` if they don't all occur in order.
+fn subsequence_spans<T: PartialEq + Copy>(
+    wanted: impl Iterator<Item = T>,
+    text: impl Iterator<Item = (usize, T)>,
+    len: impl Fn(T) -> usize,
+) -> Option<Vec<(usize, usize)>> {
+    let mut wanted = wanted.peekable();
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for (i, c) in text {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c != next {
+            continue;
+        }
+        wanted.next();
+        match spans.last_mut() {
+            Some(span) if span.1 == i => span.1 = i + len(c),
+            _ => spans.push((i, i + len(c))),
+        }
+    }
+    wanted.peek().is_none().then_some(spans)
+}
+
+/// Rates from 0 to 1 how well every token matches the title or URL, or `None` if one
+/// doesn't or the rating would fall below `floor`. A token is only matched fuzzily
+/// when it isn't found as it is.
+fn match_quality(tokens: &[Token], title: &Text, url: &Text, floor: f64) -> Option<f64> {
+    let needed = floor * tokens.len() as f64;
+    let mut total = 0.0;
+    for (i, token) in tokens.iter().enumerate() {
+        // What the tokens after this one could add at most
+        let rest = (tokens.len() - i - 1) as f64;
+        if total + 1.0 + rest < needed {
+            return None;
+        }
+        let fields = [title, url].into_iter().filter(|t| token.may_match(t));
+        let exact = fields
+            .clone()
+            .filter_map(|t| substring_match(token, t, needed - total - rest))
+            .map(|(q, _)| q)
+            .reduce(f64::max);
+        total += match exact {
+            Some(quality) => quality,
+            // Fuzzy matching is the slow part, so skip it when even the best one won't do
+            None if total + best_fuzzy(token) + rest < needed => return None,
+            None => fields
+                .filter_map(|t| fuzzy_match(token, t))
+                .map(|(q, _)| q)
+                .reduce(f64::max)?,
+        };
+    }
+    (total >= needed).then_some(total / tokens.len() as f64)
+}
+
+/// Character ranges of `text` matched by any token, merged and in order.
+fn highlights(tokens: &[Token], text: &Text) -> Vec<MatchRange> {
+    let mut bytes: Vec<(usize, usize)> = Vec::new();
+    for token in tokens {
+        match match_token(token, text) {
+            Some(Hit::Substring(start, end)) => bytes.push((start, end)),
+            Some(Hit::Fuzzy(spans)) => bytes.extend(spans),
+            None => {}
+        }
+    }
+    bytes.sort();
+
+    let mut ranges: Vec<MatchRange> = Vec::new();
+    for (start, end) in bytes {
+        let start = text.folded[..start].chars().count();
+        let end = text.folded[..end].chars().count();
+        match ranges.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => ranges.push(MatchRange { start, end }),
+        }
+    }
+    ranges
+}
+
+/// Seconds since the Unix epoch of a `CURRENT_TIMESTAMP` value; 0 if it doesn't parse.
+fn parse_timestamp(value: &str) -> i64 {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|t| t.and_utc().timestamp())
+        .unwrap_or_default()
+}
+
+/// Visit count weighted by how recent the last visit was.
+fn frecency(visit_count: i64, last_visit: i64, now: i64) -> f64 {
+    let age_days = (now - last_visit).max(0) / 86_400;
+    let weight = match age_days {
+        0..=4 => 100.0,
+        5..=14 => 70.0,
+        15..=31 => 50.0,
+        32..=90 => 30.0,
+        _ => 10.0,
+    };
+    visit_count as f64 * weight
+}
+
+fn frecency_bonus(frecency: f64) -> f64 {
+    4.0 * frecency.ln_1p()
+}
+
+#[derive(Debug, Clone)]
+struct IndexedPage {
+    url: Text,
+    title: Text,
+    mask: u64,
+    visit_count: i64,
+    last_visit: i64,
+    /// Frecency when the page was indexed. Frecency only decays, so it caps the current one.
+    rank: f64,
+}
+
+impl IndexedPage {
+    fn new(url: &str, title: &str, visit_count: i64, last_visit: i64, now: i64) -> Self {
+        let url = Text::new(url);
+        let title = Text::new(title);
+        let mask = url.mask | title.mask;
+        Self {
+            url,
+            title,
+            mask,
+            visit_count,
+            last_visit,
+            rank: frecency(visit_count, last_visit, now),
+        }
+    }
+}
+
+/// Every history entry held in memory, so suggestions don't wait on the database.
+#[derive(Debug, Default)]
+pub struct HistoryIndex {
+    /// Highest rank first, so a search can stop once no page left could make the list.
+    pages: Vec<IndexedPage>,
+    by_url: HashMap<String, usize>,
+}
+
+impl HistoryIndex {
+    pub fn new(entries: Vec<HistoryEntry>, now: i64) -> Self {
+        let mut pages: Vec<IndexedPage> = entries
+            .into_iter()
+            .map(|entry| {
+                IndexedPage::new(
+                    &entry.url,
+                    entry.title.as_deref().unwrap_or_default(),
+                    entry.visit_count as i64,
+                    parse_timestamp(&entry.last_visit),
+                    now,
+                )
+            })
+            .collect();
+        pages.sort_by(|a, b| b.rank.total_cmp(&a.rank));
+        let by_url = pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.url.original.clone(), i))
+            .collect();
+        Self { pages, by_url }
+    }
+
+    /// Mirrors `storage::add_history_entry`.
+    pub fn record_visit(&mut self, url: &str, title: Option<&str>, now: i64) {
+        let i = match self.by_url.get(url) {
+            Some(&i) => {
+                let page = &mut self.pages[i];
+                let title = title.unwrap_or(&page.title.original).to_string();
+                *page = IndexedPage::new(url, &title, page.visit_count + 1, now, now);
+                i
+            }
+            None => {
+                self.by_url.insert(url.to_string(), self.pages.len());
+                let title = title.unwrap_or_default();
+                self.pages.push(IndexedPage::new(url, title, 1, now, now));
+                self.pages.len() - 1
+            }
+        };
+
+        // Move the page up ahead of those ranked lower, which keeps the order
+        let rank = self.pages[i].rank;
+        let j = self.pages[..i].partition_point(|p| p.rank >= rank);
+        self.pages[j..=i].rotate_right(1);
+        for k in j..=i {
+            if let Some(index) = self.by_url.get_mut(&self.pages[k].url.original) {
+                *index = k;
+            }
+        }
+    }
+
+    fn frecency(&self, url: &str, now: i64) -> f64 {
+        self.by_url
+            .get(url)
+            .map(|&i| frecency(self.pages[i].visit_count, self.pages[i].last_visit, now))
+            .unwrap_or_default()
+    }
+}
+
+static HISTORY_INDEX: Lazy<RwLock<HistoryIndex>> =
+    Lazy::new(|| RwLock::new(HistoryIndex::default()));
+
+pub fn load_history_index(entries: Vec<HistoryEntry>) {
+    let index = HistoryIndex::new(entries, chrono::Utc::now().timestamp());
+    *HISTORY_INDEX.write().unwrap() = index;
+}
+
+pub fn record_visit(url: &str, title: Option<&str>) {
+    let now = chrono::Utc::now().timestamp();
+    HISTORY_INDEX.write().unwrap().record_visit(url, title, now);
+}
+
+pub fn clear_history_index() {
+    *HISTORY_INDEX.write().unwrap() = HistoryIndex::default();
+}
+
+// Kept in memory like the history index so typing never waits on the database
+static BOOKMARKS: Lazy<RwLock<Vec<Bookmark>>> = Lazy::new(|| RwLock::new(Vec::new()));
+static SEARCH_ENGINE: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new("google".to_string()));
+
+pub fn load_bookmarks(bookmarks: Vec<Bookmark>) {
+    *BOOKMARKS.write().unwrap() = bookmarks;
+}
+
+pub fn set_search_engine(engine: &str) {
+    *SEARCH_ENGINE.write().unwrap() = engine.to_string();
+}
+
+/// Reloads the cached bookmarks whenever they change.
+pub fn start_bookmark_sync(db: Database) {
+    tauri::async_runtime::spawn(async move {
+        let mut events = subscribe();
+        loop {
+            match events.recv().await {
+                Ok(BrowserEvent::BookmarksChanged) | Err(RecvError::Lagged(_)) => {}
+                Ok(_) => continue,
+                Err(RecvError::Closed) => return,
+            }
+            match crate::storage::get_bookmarks(&db).await {
+                Ok(bookmarks) => load_bookmarks(bookmarks),
+                Err(e) => warn!("Failed to reload bookmarks: {}", e),
+            }
+        }
+    });
+}
+
+/// The results page of `engine` for `query`. Unknown engines fall back to Google.
+pub fn search_url(engine: &str, query: &str) -> String {
+    let base = match engine {
+        "duckduckgo" => "https://duckduckgo.com/?q=",
+        "bing" => "https://www.bing.com/search?q=",
+        _ => "https://www.google.com/search?q=",
+    };
+    let mut url = url::Url::parse(base).expect("search engine URLs are valid");
+    url.query_pairs_mut().clear().append_pair("q", query);
+    url.to_string()
+}
+
+/// Keeps the `limit` highest scores seen, highest first, and tells whether `score` is
+/// among them.
+fn keep_best(scores: &mut Vec<f64>, score: f64, limit: usize) -> bool {
+    let index = scores.partition_point(|s| *s >= score);
+    if index < limit {
+        scores.insert(index, score);
+        scores.truncate(limit);
+    }
+    index < limit
+}
+
+struct Candidate<'a> {
+    kind: SuggestionKind,
+    url: &'a Text,
+    title: &'a Text,
+    tab_id: Option<&'a TabId>,
+    score: f64,
+}
+
+/// Ranks open tabs and the cached bookmarks and history against `query`.
+pub fn get_suggestions(query: &str, tabs: &[Tab]) -> Vec<Suggestion> {
+    let bookmarks = BOOKMARKS.read().unwrap();
+    let history = HISTORY_INDEX.read().unwrap();
+    let search_engine = SEARCH_ENGINE.read().unwrap();
+    suggest(
+        query,
+        tabs,
+        &bookmarks,
+        &history,
+        &search_engine,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+/// Ranks matches for `query`, best first, with the search fallback last.
+///
+/// Each URL is suggested once: as an open tab if one shows it, else as a bookmark,
+/// else from history. Visits lift bookmarks and history by frecency.
+pub fn suggest(
+    query: &str,
+    tabs: &[Tab],
+    bookmarks: &[Bookmark],
+    history: &HistoryIndex,
+    search_engine: &str,
+    now: i64,
+) -> Vec<Suggestion> {
+    let query = query.trim();
+    let tokens = tokens(query);
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let tab_texts: Vec<(Text, Text, &Tab)> = tabs
+        .iter()
+        .filter(|t| t.url != "about:blank")
+        .map(|t| (Text::new(&t.url), Text::new(&t.title), t))
+        .collect();
+    let bookmark_texts: Vec<(Text, Text)> = bookmarks
+        .iter()
+        .filter(|b| !tab_texts.iter().any(|(_, _, t)| t.url == b.url))
+        .map(|b| (Text::new(&b.url), Text::new(&b.title)))
+        .collect();
+    let shown: HashSet<usize> = tab_texts
+        .iter()
+        .map(|(u, _, _)| u)
+        .chain(bookmark_texts.iter().map(|(u, _)| u))
+        .filter_map(|u| history.by_url.get(&u.original).copied())
+        .collect();
+
+    let mut candidates = Vec::new();
+    for (url, title, tab) in &tab_texts {
+        if let Some(quality) = match_quality(&tokens, title, url, 0.0) {
+            candidates.push(Candidate {
+                kind: SuggestionKind::Tab,
+                url,
+                title,
+                tab_id: Some(&tab.id),
+                score: quality * 100.0 + TAB_BONUS,
+            });
+        }
+    }
+    for (url, title) in &bookmark_texts {
+        if let Some(quality) = match_quality(&tokens, title, url, 0.0) {
+            let visits = frecency_bonus(history.frecency(&url.original, now));
+            candidates.push(Candidate {
+                kind: SuggestionKind::Bookmark,
+                url,
+                title,
+                tab_id: None,
+                score: quality * 100.0 + BOOKMARK_BONUS + visits,
+            });
+        }
+    }
+    let limit = MAX_SUGGESTIONS - 1;
+    let mut best_scores: Vec<f64> = Vec::with_capacity(limit + 1);
+    for candidate in &candidates {
+        keep_best(&mut best_scores, candidate.score, limit);
+    }
+    let mut bound = (f64::INFINITY, f64::INFINITY);
+    for (i, page) in history.pages.iter().enumerate() {
+        // The best a page can score is a perfect match at its rank
+        if page.rank != bound.0 {
+            bound = (page.rank, 100.0 + frecency_bonus(page.rank));
+        }
+        let cutoff = (best_scores.len() == limit).then(|| best_scores[limit - 1]);
+        if cutoff.is_some_and(|cutoff| cutoff >= bound.1) {
+            break;
+        }
+
+        // Every kind of match needs all of a token's characters somewhere in the page
+        if tokens.iter().any(|t| t.mask & !page.mask != 0) || shown.contains(&i) {
+            continue;
+        }
+        let visits = frecency_bonus(frecency(page.visit_count, page.last_visit, now));
+        let floor = cutoff.map_or(0.0, |cutoff| (cutoff - visits) / 100.0);
+        if let Some(quality) = match_quality(&tokens, &page.title, &page.url, floor) {
+            let score = quality * 100.0 + visits;
+            if !keep_best(&mut best_scores, score, limit) {
+                continue;
+            }
+            candidates.push(Candidate {
+                kind: SuggestionKind::History,
+                url: &page.url,
+                title: &page.title,
+                tab_id: None,
+                score,
+            });
+        }
+    }
+
+    // Only the few shown need sorting
+    let by_score = |a: &Candidate, b: &Candidate| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.url.original.cmp(&b.url.original))
+    };
+    if candidates.len() > limit {
+        candidates.select_nth_unstable_by(limit, by_score);
+        candidates.truncate(limit);
+    }
+    candidates.sort_by(by_score);
+
+    let mut suggestions: Vec<Suggestion> = candidates
+        .into_iter()
+        .map(|c| Suggestion {
+            kind: c.kind,
+            url: c.url.original.clone(),
+            title: c.title.original.clone(),
+            tab_id: c.tab_id.cloned(),
+            title_matches: highlights(&tokens, c.title),
+            url_matches: highlights(&tokens, c.url),
+            score: c.score,
+        })
+        .collect();
+
+    let prefix = "Search for \"";
+    suggestions.push(Suggestion {
+        kind: SuggestionKind::Search,
+        url: search_url(search_engine, query),
+        title: format!("{}{}\"", prefix, query),
+        tab_id: None,
+        title_matches: vec![MatchRange {
+            start: prefix.len(),
+            end: prefix.len() + query.chars().count(),
+        }],
+        url_matches: Vec::new(),
+        score: 0.0,
+    });
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::LifecycleState;
+    use crate::security::TabSecurity;
+    use std::time::{Duration, Instant};
+
+    fn visit(url: &str, title: &str, visit_count: i32, last_visit: i64) -> HistoryEntry {
+        HistoryEntry {
+            id: 0,
+            url: url.to_string(),
+            title: Some(title.to_string()),
+            visit_count,
+            last_visit: chrono::DateTime::from_timestamp(last_visit, 0)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        }
+    }
+
+    fn tab(id: &str, url: &str) -> Tab {
+        Tab {
+            id: id.to_string(),
+            url: url.to_string(),
+            title: String::new(),
+            favicon: None,
+            window_id: "main".to_string(),
+            position: 0,
+            is_active: true,
+            lifecycle_state: LifecycleState::Active,
+            opener_id: None,
+            browser_context_id: None,
+            group_id: None,
+            pinned: false,
+            security: TabSecurity::default(),
+        }
+    }
+
+    #[test]
+    fn ranks_open_tabs_bookmarks_and_history() {
+        let now = chrono::Utc::now().timestamp();
+        let mut history = HistoryIndex::new(
+            vec![
+                visit("https://github.com/rust-lang", "Rust", 50, now),
+                visit("https://gist.github.com/", "Gists", 3, now),
+                visit("https://example.com/", "Example", 9, now),
+            ],
+            now,
+        );
+        history.record_visit("https://docs.rs/", Some("Docs.rs"), now);
+        let bookmarks = vec![Bookmark {
+            id: 1,
+            url: "https://github.com/notifications".to_string(),
+            title: "GitHub notifications".to_string(),
+            folder_id: None,
+            created_at: String::new(),
+        }];
+        let tabs = vec![tab("tab-1", "https://github.com/rust-lang")];
+
+        let suggestions = suggest("git", &tabs, &bookmarks, &history, "duckduckgo", now);
+        let found: Vec<(SuggestionKind, &str)> = suggestions
+            .iter()
+            .map(|s| (s.kind, s.url.as_str()))
+            .collect();
+        // The open tab stands in for its history entry
+        assert_eq!(
+            found,
+            [
+                (SuggestionKind::Tab, "https://github.com/rust-lang"),
+                (SuggestionKind::Bookmark, "https://github.com/notifications"),
+                (SuggestionKind::History, "https://gist.github.com/"),
+                (SuggestionKind::Search, "https://duckduckgo.com/?q=git"),
+            ]
+        );
+        assert_eq!(suggestions[0].tab_id.as_deref(), Some("tab-1"));
+        assert_eq!(
+            suggestions[0].url_matches,
+            [MatchRange { start: 8, end: 11 }]
+        );
+        assert_eq!(
+            suggestions[1].title_matches,
+            [MatchRange { start: 0, end: 3 }]
+        );
+
+        // Characters in order match too, below exact ones
+        let fuzzy = suggest("dcsrs", &tabs, &bookmarks, &history, "google", now);
+        assert_eq!(fuzzy[0].url, "https://docs.rs/");
+        assert_eq!(
+            fuzzy[0].title_matches,
+            [
+                MatchRange { start: 0, end: 1 },
+                MatchRange { start: 2, end: 4 },
+                MatchRange { start: 5, end: 7 },
+            ]
+        );
+        assert_eq!(fuzzy.len(), 2);
+    }
+
+    /// A wall-clock benchmark, too noisy for shared CI runners. Run it with
+    /// `cargo test -- --ignored` after changing how suggestions are matched.
+    #[test]
+    #[ignore = "timing benchmark"]
+    fn suggestions_stay_fast_with_a_large_history() {
+        let now = chrono::Utc::now().timestamp();
+        let words = [
+            "rust", "docs", "news", "mail", "maps", "video", "shop", "wiki",
+        ];
+        let entries = (0..100_000)
+            .map(|i| {
+                let word = words[i % words.len()];
+                let url = format!("https://{}{}.example.com/page/{}", word, i % 997, i);
+                let title = format!("{} page {}", word, i);
+                visit(
+                    &url,
+                    &title,
+                    (i % 40) as i32 + 1,
+                    now - (i as i64 * 7919) % 31_536_000,
+                )
+            })
+            .collect();
+        let history = HistoryIndex::new(entries, now);
+
+        for query in ["rust", "docs page 42", "mailexample", "zzzz"] {
+            let started = Instant::now();
+            let suggestions = suggest(query, &[], &[], &history, "google", now);
+            let elapsed = started.elapsed();
+
+            assert!(!suggestions.is_empty());
+            // Generous for unoptimized builds; a keystroke should never stall the UI
+            assert!(
+                elapsed < Duration::from_millis(250),
+                "\"{}\" took {:?}",
+                query,
+                elapsed
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::browser::get_browser_manager;
+use crate::cdp::{recv_event, CdpConnection, CdpEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+/// What to do when a page on a site opens a new window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PopupPolicy {
+    /// Open the popup as a new tab next to its opener.
+    #[default]
+    Allow,
+    /// Close the popup as soon as Chrome creates it.
+    Block,
+    /// Close the popup and load its URL in the opener tab instead.
+    Redirect,
+}
+
+impl PopupPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PopupPolicy::Allow => "allow",
+            PopupPolicy::Block => "block",
+            PopupPolicy::Redirect => "redirect",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "block" => PopupPolicy::Block,
+            "redirect" => PopupPolicy::Redirect,
+            _ => PopupPolicy::Allow,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PopupRule {
+    pub host: String,
+    pub policy: PopupPolicy,
+}
+
+/// Returns the host a popup rule is keyed on, if the URL has one.
+pub fn popup_host(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|h| h.trim_start_matches("www.").to_lowercase())
+}
+
+/// Watches browser-level target events and routes page targets opened by our tabs
+/// through the popup policy.
+pub async fn watch_popups(mut events: broadcast::Receiver<CdpEvent>) {
+    while let Some(event) = recv_event(&mut events).await {
+        if event.session_id.is_some() || event.method != "Target.targetCreated" {
+            continue;
+        }
+
+        let info = &event.params["targetInfo"];
+        if info["type"] != "page" || info["openerId"].as_str().is_none() {
+            continue;
+        }
+
+        let info = info.clone();
+        tokio::spawn(async move {
+            if let Some(manager) = get_browser_manager() {
+                let manager = manager.lock().await;
+                if let Err(e) = manager.handle_popup(&info).await {
+                    warn!("Failed to handle popup: {}", e);
+                }
+            }
+        });
+    }
+    debug!("Popup watcher stopped");
+}
+
+/// Waits for a freshly opened target to report a real URL.
+///
+/// `window.open` targets are often created on `about:blank` and navigate right after.
+pub async fn wait_for_target_url(
+    conn: &CdpConnection,
+    target_id: &str,
+    initial_url: &str,
+) -> Option<String> {
+    let is_real = |url: &str| !url.is_empty() && url != "about:blank";
+    if is_real(initial_url) {
+        return Some(initial_url.to_string());
+    }
+
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let info = conn
+            .send(
+                None,
+                "Target.getTargetInfo",
+                json!({ "targetId": target_id }),
+            )
+            .await
+            .ok()?;
+        if let Some(url) = info["targetInfo"]["url"].as_str().filter(|u| is_real(u)) {
+            return Some(url.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Connect directly.
+    #[default]
+    Direct,
+    Http,
+    Https,
+    Socks5,
+    /// Use the PAC script at `pac_url`. Only supported for the default profile.
+    Pac,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    pub host: String,
+    pub port: u16,
+    /// Hosts that bypass the proxy, e.g. `localhost` or `*.corp.example.com`.
+    pub bypass_list: Vec<String>,
+    pub pac_url: Option<String>,
+}
+
+/// The login for a proxy that asks for one.
+///
+/// Kept out of `ProxyConfig` so it's stored apart from the settings and never sent back
+/// to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProxyCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl ProxyConfig {
+    /// The `scheme://host:port` form Chrome expects, if a fixed proxy is configured.
+    pub fn server(&self) -> Option<String> {
+        let scheme = match self.mode {
+            ProxyMode::Http => "http",
+            ProxyMode::Https => "https",
+            ProxyMode::Socks5 => "socks5",
+            ProxyMode::Direct | ProxyMode::Pac => return None,
+        };
+        if self.host.is_empty() {
+            return None;
+        }
+        Some(format!("{}://{}:{}", scheme, self.host, self.port))
+    }
+
+    pub fn bypass(&self) -> Option<String> {
+        if self.bypass_list.is_empty() {
+            None
+        } else {
+            Some(self.bypass_list.join(";"))
+        }
+    }
+
+    /// Command-line flags that apply this proxy to the whole Chrome profile.
+    pub fn chrome_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        match self.mode {
+            ProxyMode::Direct => {}
+            ProxyMode::Pac => {
+                if let Some(pac_url) = self.pac_url.as_deref().filter(|u| !u.is_empty()) {
+                    args.push(format!("--proxy-pac-url={}", pac_url));
+                }
+            }
+            _ => {
+                if let Some(server) = self.server() {
+                    args.push(format!("--proxy-server={}", server));
+                }
+            }
+        }
+        if self.mode != ProxyMode::Direct {
+            if let Some(bypass) = self.bypass() {
+                args.push(format!("--proxy-bypass-list={}", bypass));
+            }
+        }
+        args
+    }
+
+    /// Parameters for `Target.createBrowserContext`.
+    pub fn browser_context_params(&self) -> Value {
+        let mut params = json!({ "disposeOnDetach": false });
+        if let Some(server) = self.server() {
+            params["proxyServer"] = json!(server);
+            if let Some(bypass) = self.bypass() {
+                params["proxyBypassList"] = json!(bypass);
+            }
+        }
+        params
+    }
+}
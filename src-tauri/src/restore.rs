@@ -0,0 +1,69 @@
+use crate::browser::get_browser_manager;
+use crate::db::Database;
+use crate::events::subscribe;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// A set of tabs the user saved under a name, to reopen later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedSession {
+    pub id: i64,
+    pub name: String,
+    pub tab_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub url: String,
+    pub title: String,
+    pub favicon: Option<String>,
+}
+
+/// How long to wait for a burst of tab changes to settle before writing.
+const WRITE_DELAY: Duration = Duration::from_millis(500);
+
+/// Keeps the `tabs` table in step with the open tabs.
+///
+/// Writes once straight away, then again shortly after any tab change.
+pub fn start_tab_persistence(db: Database) {
+    tauri::async_runtime::spawn(async move {
+        let mut events = subscribe();
+        loop {
+            save_open_tabs(&db).await;
+
+            loop {
+                match events.recv().await {
+                    Ok(event) if event.changes_tabs() => break,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => return,
+                }
+            }
+            tokio::time::sleep(WRITE_DELAY).await;
+            while events.try_recv().is_ok() {}
+        }
+    });
+}
+
+async fn save_open_tabs(db: &Database) {
+    let Some(manager) = get_browser_manager() else {
+        return;
+    };
+    let (tabs, groups, windows) = {
+        let manager = manager.lock().await;
+        (
+            manager.get_tabs().await,
+            manager.get_tab_groups().await,
+            manager.get_windows().await,
+        )
+    };
+
+    match crate::storage::save_tabs(db, &tabs, &groups, &windows).await {
+        Ok(()) => debug!("Saved {} open tabs", tabs.len()),
+        Err(e) => warn!("Failed to save open tabs: {}", e),
+    }
+}
@@ -0,0 +1,376 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// A user-defined rule applied to requests as they leave a tab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestRule {
+    pub id: i64,
+    pub name: String,
+    /// Glob matched against the full URL; `*` matches any run of characters.
+    pub url_pattern: String,
+    /// CDP resource type such as `Document`, `XHR`, `Fetch` or `Script`. `None` matches all.
+    pub resource_type: Option<String>,
+    pub action: RuleAction,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Adds or overrides request headers and strips others.
+    ModifyHeaders {
+        #[serde(default)]
+        set: HashMap<String, String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    /// Answers with a 302 to `url`.
+    Redirect { url: String },
+    /// Answers without touching the network, from `body` or the file at `file_path`.
+    Fulfill {
+        #[serde(default = "default_status")]
+        status: u16,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        body: Option<String>,
+        file_path: Option<String>,
+    },
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl RequestRule {
+    pub fn matches(&self, url: &str, resource_type: &str) -> bool {
+        self.enabled
+            && self
+                .resource_type
+                .as_deref()
+                .map_or(true, |t| t.eq_ignore_ascii_case(resource_type))
+            && glob_match(&self.url_pattern, url)
+    }
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of characters.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Picks the CDP call that answers a `Fetch.requestPaused` event.
+///
+/// The first enabled rule that matches wins; unmatched requests continue untouched.
+pub async fn resolve_paused_request(
+    rules: &[RequestRule],
+    params: &Value,
+) -> (&'static str, Value) {
+    let request_id = params["requestId"].clone();
+    let url = params["request"]["url"].as_str().unwrap_or_default();
+    let resource_type = params["resourceType"].as_str().unwrap_or_default();
+
+    let Some(rule) = rules.iter().find(|r| r.matches(url, resource_type)) else {
+        return ("Fetch.continueRequest", json!({ "requestId": request_id }));
+    };
+
+    match &rule.action {
+        RuleAction::ModifyHeaders { set, remove } => {
+            let mut headers: Vec<(String, String)> = params["request"]["headers"]
+                .as_object()
+                .map(|h| {
+                    h.iter()
+                        .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            headers.retain(|(name, _)| {
+                !remove.iter().any(|r| r.eq_ignore_ascii_case(name))
+                    && !set.keys().any(|s| s.eq_ignore_ascii_case(name))
+            });
+            headers.extend(set.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+            (
+                "Fetch.continueRequest",
+                json!({ "requestId": request_id, "headers": header_entries(&headers) }),
+            )
+        }
+        RuleAction::Redirect { url } => (
+            "Fetch.fulfillRequest",
+            json!({
+                "requestId": request_id,
+                "responseCode": 302,
+                "responseHeaders": [{ "name": "Location", "value": url }],
+            }),
+        ),
+        RuleAction::Fulfill {
+            status,
+            headers,
+            body,
+            file_path,
+        } => {
+            let bytes = match (file_path, body) {
+                (Some(path), _) => tokio::fs::read(path).await.unwrap_or_else(|e| {
+                    warn!("Rule {} can't read {}: {}", rule.id, path, e);
+                    Vec::new()
+                }),
+                (None, Some(body)) => body.clone().into_bytes(),
+                (None, None) => Vec::new(),
+            };
+
+            let mut headers: Vec<(String, String)> = headers
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if !headers
+                .iter()
+                .any(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+            {
+                let content_type = file_path
+                    .as_deref()
+                    .map(content_type_for)
+                    .unwrap_or("text/plain; charset=utf-8");
+                headers.push(("Content-Type".to_string(), content_type.to_string()));
+            }
+
+            (
+                "Fetch.fulfillRequest",
+                json!({
+                    "requestId": request_id,
+                    "responseCode": status,
+                    "responseHeaders": header_entries(&headers),
+                    "body": base64::engine::general_purpose::STANDARD.encode(bytes),
+                }),
+            )
+        }
+    }
+}
+
+fn header_entries(headers: &[(String, String)]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn content_type_for(path: &str) -> &'static str {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "json" => "application/json",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript",
+        "css" => "text/css",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "xml" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(url_pattern: &str, action: RuleAction) -> RequestRule {
+        RequestRule {
+            id: 1,
+            name: "Test".to_string(),
+            url_pattern: url_pattern.to_string(),
+            resource_type: None,
+            action,
+            enabled: true,
+        }
+    }
+
+    fn paused(url: &str, resource_type: &str, headers: Value) -> Value {
+        json!({
+            "requestId": "r1",
+            "resourceType": resource_type,
+            "request": { "url": url, "headers": headers },
+        })
+    }
+
+    fn header<'a>(headers: &'a Value, name: &str) -> Option<&'a str> {
+        headers
+            .as_array()?
+            .iter()
+            .find(|h| h["name"] == name)
+            .and_then(|h| h["value"].as_str())
+    }
+
+    #[test]
+    fn globs_match_runs_of_characters_between_anchored_ends() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "https://a.test/"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "https://a.test/"));
+        assert!(glob_match("https://a.test/", "https://a.test/"));
+        assert!(!glob_match("https://a.test/", "https://a.test/x"));
+
+        // Both ends are anchored unless they are a star
+        assert!(glob_match("https://a.test/*", "https://a.test/x/y"));
+        assert!(!glob_match(
+            "https://a.test/*",
+            "http://evil.test/https://a.test/"
+        ));
+        assert!(glob_match("*.js", "https://a.test/app.js"));
+        assert!(!glob_match("*.js", "https://a.test/app.js?v=2"));
+
+        // Stars in the middle match in order and may match nothing
+        assert!(glob_match("*://*.test/*/b", "https://a.test/a/b"));
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(!glob_match("a*b*c", "acb"));
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[tokio::test]
+    async fn unmatched_disabled_and_other_type_requests_continue_untouched() {
+        let mut disabled = rule(
+            "*",
+            RuleAction::Redirect {
+                url: "https://b.test/".to_string(),
+            },
+        );
+        disabled.enabled = false;
+        let mut scripts_only = rule(
+            "*",
+            RuleAction::Redirect {
+                url: "https://b.test/".to_string(),
+            },
+        );
+        scripts_only.resource_type = Some("script".to_string());
+        let other_host = rule(
+            "https://c.test/*",
+            RuleAction::Redirect {
+                url: "https://b.test/".to_string(),
+            },
+        );
+        let rules = [disabled, scripts_only, other_host];
+
+        let (method, params) =
+            resolve_paused_request(&rules, &paused("https://a.test/", "Document", json!({}))).await;
+        assert_eq!(method, "Fetch.continueRequest");
+        assert_eq!(params, json!({ "requestId": "r1" }));
+
+        let (method, _) =
+            resolve_paused_request(&rules, &paused("https://a.test/a.js", "Script", json!({})))
+                .await;
+        assert_eq!(method, "Fetch.fulfillRequest");
+    }
+
+    #[tokio::test]
+    async fn header_rules_set_and_remove_headers_ignoring_case() {
+        let rules = [rule(
+            "*",
+            RuleAction::ModifyHeaders {
+                set: HashMap::from([("user-agent".to_string(), "Aether".to_string())]),
+                remove: vec!["COOKIE".to_string()],
+            },
+        )];
+        let request = paused(
+            "https://a.test/",
+            "XHR",
+            json!({ "User-Agent": "Chrome", "Cookie": "id=1", "Accept": "*/*" }),
+        );
+
+        let (method, params) = resolve_paused_request(&rules, &request).await;
+        assert_eq!(method, "Fetch.continueRequest");
+        let headers = &params["headers"];
+        assert_eq!(headers.as_array().unwrap().len(), 2);
+        assert_eq!(header(headers, "user-agent"), Some("Aether"));
+        assert_eq!(header(headers, "Accept"), Some("*/*"));
+        assert_eq!(header(headers, "User-Agent"), None);
+        assert_eq!(header(headers, "Cookie"), None);
+    }
+
+    #[tokio::test]
+    async fn redirect_rules_answer_with_a_302() {
+        let rules = [rule(
+            "*://old.test/*",
+            RuleAction::Redirect {
+                url: "https://new.test/".to_string(),
+            },
+        )];
+
+        let (method, params) =
+            resolve_paused_request(&rules, &paused("https://old.test/a", "Document", json!({})))
+                .await;
+        assert_eq!(method, "Fetch.fulfillRequest");
+        assert_eq!(params["responseCode"], 302);
+        assert_eq!(
+            header(&params["responseHeaders"], "Location"),
+            Some("https://new.test/")
+        );
+    }
+
+    #[tokio::test]
+    async fn fulfill_rules_answer_from_the_body_or_a_file() {
+        let body = rule(
+            "*",
+            RuleAction::Fulfill {
+                status: 404,
+                headers: HashMap::new(),
+                body: Some("gone".to_string()),
+                file_path: None,
+            },
+        );
+        let (_, params) =
+            resolve_paused_request(&[body], &paused("https://a.test/", "Fetch", json!({}))).await;
+        assert_eq!(params["responseCode"], 404);
+        assert_eq!(
+            params["body"],
+            base64::engine::general_purpose::STANDARD.encode("gone")
+        );
+        assert_eq!(
+            header(&params["responseHeaders"], "Content-Type"),
+            Some("text/plain; charset=utf-8")
+        );
+
+        let path = std::env::temp_dir().join(format!("aether-rule-{}.json", std::process::id()));
+        std::fs::write(&path, "{}").unwrap();
+        let file = rule(
+            "*",
+            RuleAction::Fulfill {
+                status: 200,
+                headers: HashMap::from([("X-Mock".to_string(), "1".to_string())]),
+                body: Some("ignored".to_string()),
+                file_path: Some(path.to_string_lossy().to_string()),
+            },
+        );
+        let (_, params) =
+            resolve_paused_request(&[file], &paused("https://a.test/", "Fetch", json!({}))).await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            params["body"],
+            base64::engine::general_purpose::STANDARD.encode("{}")
+        );
+        assert_eq!(header(&params["responseHeaders"], "X-Mock"), Some("1"));
+        assert_eq!(
+            header(&params["responseHeaders"], "Content-Type"),
+            Some("application/json")
+        );
+    }
+}
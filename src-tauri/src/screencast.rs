@@ -0,0 +1,214 @@
+use crate::browser::TabId;
+use crate::cdp::{recv_event, CdpEvent, CdpSession};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+pub const SCREENCAST_FRAME_EVENT: &str = "screencast-frame";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreencastSettings {
+    /// `"jpeg"` or `"png"`.
+    pub format: String,
+    /// JPEG quality, 0-100.
+    pub quality: u8,
+    pub max_fps: u32,
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+impl Default for ScreencastSettings {
+    fn default() -> Self {
+        Self {
+            format: "jpeg".to_string(),
+            quality: 70,
+            max_fps: 30,
+            max_width: None,
+            max_height: None,
+        }
+    }
+}
+
+impl ScreencastSettings {
+    pub fn to_cdp_params(&self) -> Value {
+        let mut params = json!({
+            "format": self.format,
+            "quality": self.quality.min(100),
+        });
+        if let Some(width) = self.max_width {
+            params["maxWidth"] = json!(width);
+        }
+        if let Some(height) = self.max_height {
+            params["maxHeight"] = json!(height);
+        }
+        params
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreencastFrame {
+    pub tab_id: TabId,
+    /// Base64-encoded image in the requested format.
+    pub data: String,
+    pub format: String,
+    pub device_width: f64,
+    pub device_height: f64,
+    pub page_scale_factor: f64,
+    pub offset_top: f64,
+    pub scroll_offset_x: f64,
+    pub scroll_offset_y: f64,
+    pub timestamp: Option<f64>,
+}
+
+/// Forwards screencast frames for one tab to the frontend until the stream ends.
+///
+/// Chrome won't send another frame until the previous one is acked, so holding
+/// the ack back is what enforces `max_fps`.
+pub async fn pump_frames(
+    app_handle: AppHandle,
+    session: CdpSession,
+    tab_id: TabId,
+    settings: ScreencastSettings,
+    mut events: broadcast::Receiver<CdpEvent>,
+) {
+    let min_interval = Duration::from_secs(1) / settings.max_fps.max(1);
+    let mut last_frame: Option<Instant> = None;
+
+    while let Some(event) = recv_event(&mut events).await {
+        if !event.is_for(&session) || event.method != "Page.screencastFrame" {
+            continue;
+        }
+
+        let params = &event.params;
+        let metadata = &params["metadata"];
+        let frame = ScreencastFrame {
+            tab_id: tab_id.clone(),
+            data: params["data"].as_str().unwrap_or_default().to_string(),
+            format: settings.format.clone(),
+            device_width: metadata["deviceWidth"].as_f64().unwrap_or_default(),
+            device_height: metadata["deviceHeight"].as_f64().unwrap_or_default(),
+            page_scale_factor: metadata["pageScaleFactor"].as_f64().unwrap_or(1.0),
+            offset_top: metadata["offsetTop"].as_f64().unwrap_or_default(),
+            scroll_offset_x: metadata["scrollOffsetX"].as_f64().unwrap_or_default(),
+            scroll_offset_y: metadata["scrollOffsetY"].as_f64().unwrap_or_default(),
+            timestamp: metadata["timestamp"].as_f64(),
+        };
+
+        if let Err(e) = app_handle.emit(SCREENCAST_FRAME_EVENT, frame) {
+            warn!("Failed to emit screencast frame: {}", e);
+        }
+
+        if let Some(last) = last_frame {
+            let elapsed = last.elapsed();
+            if elapsed < min_interval {
+                tokio::time::sleep(min_interval - elapsed).await;
+            }
+        }
+        last_frame = Some(Instant::now());
+
+        let ack = session
+            .send(
+                "Page.screencastFrameAck",
+                json!({ "sessionId": params["sessionId"] }),
+            )
+            .await;
+        if let Err(e) = ack {
+            debug!("Screencast for tab {} ended: {}", tab_id, e);
+            break;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseEventKind {
+    MousePressed,
+    MouseReleased,
+    MouseMoved,
+    MouseWheel,
+}
+
+/// A mouse event from the frontend, in CSS pixels relative to the page viewport.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseInput {
+    pub kind: MouseEventKind,
+    pub x: f64,
+    pub y: f64,
+    /// `"none"`, `"left"`, `"middle"`, `"right"`, `"back"` or `"forward"`.
+    #[serde(default)]
+    pub button: Option<String>,
+    #[serde(default)]
+    pub click_count: u32,
+    #[serde(default)]
+    pub delta_x: f64,
+    #[serde(default)]
+    pub delta_y: f64,
+    /// Bit field: Alt=1, Ctrl=2, Meta=4, Shift=8.
+    #[serde(default)]
+    pub modifiers: u32,
+}
+
+impl MouseInput {
+    pub fn to_cdp_params(&self) -> Value {
+        json!({
+            "type": self.kind,
+            "x": self.x,
+            "y": self.y,
+            "button": self.button.as_deref().unwrap_or("none"),
+            "clickCount": self.click_count,
+            "deltaX": self.delta_x,
+            "deltaY": self.delta_y,
+            "modifiers": self.modifiers,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyEventKind {
+    KeyDown,
+    KeyUp,
+    RawKeyDown,
+    Char,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyInput {
+    pub kind: KeyEventKind,
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub windows_virtual_key_code: Option<i64>,
+    #[serde(default)]
+    pub modifiers: u32,
+}
+
+impl KeyInput {
+    pub fn to_cdp_params(&self) -> Value {
+        let mut params = json!({
+            "type": self.kind,
+            "modifiers": self.modifiers,
+        });
+        if let Some(key) = &self.key {
+            params["key"] = json!(key);
+        }
+        if let Some(code) = &self.code {
+            params["code"] = json!(code);
+        }
+        if let Some(text) = &self.text {
+            params["text"] = json!(text);
+        }
+        if let Some(key_code) = self.windows_virtual_key_code {
+            params["windowsVirtualKeyCode"] = json!(key_code);
+        }
+        params
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Which JavaScript world a script runs in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptWorld {
+    /// Aether's isolated world: shares the DOM but not globals with the page. Globals a
+    /// script sets stay for later scripts until the page navigates.
+    #[default]
+    Isolated,
+    /// The page's own world, for scripts that need its globals.
+    Main,
+}
+
+/// Outcome of running a script. Exactly one of `value` and `exception` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationResult {
+    pub value: Option<Value>,
+    pub exception: Option<ScriptException>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptException {
+    pub message: String,
+    pub class_name: Option<String>,
+    pub line_number: i64,
+    pub column_number: i64,
+    pub url: Option<String>,
+    pub stack: Vec<StackFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub function_name: String,
+    pub url: String,
+    pub line_number: i64,
+    pub column_number: i64,
+}
+
+impl EvaluationResult {
+    /// Builds a result from a `Runtime.evaluate` or `Runtime.callFunctionOn` response
+    /// made with `returnByValue`.
+    pub fn from_cdp(result: &Value) -> Self {
+        match result.get("exceptionDetails") {
+            Some(details) => Self {
+                value: None,
+                exception: Some(ScriptException::from_cdp(details)),
+            },
+            None => Self {
+                value: Some(result["result"]["value"].clone()),
+                exception: None,
+            },
+        }
+    }
+}
+
+impl ScriptException {
+    fn from_cdp(details: &Value) -> Self {
+        let exception = &details["exception"];
+        let message = exception["description"]
+            .as_str()
+            .or_else(|| details["text"].as_str())
+            .unwrap_or("Uncaught exception")
+            .to_string();
+
+        let stack = details["stackTrace"]["callFrames"]
+            .as_array()
+            .map(|frames| {
+                frames
+                    .iter()
+                    .map(|frame| StackFrame {
+                        function_name: frame["functionName"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        url: frame["url"].as_str().unwrap_or_default().to_string(),
+                        line_number: frame["lineNumber"].as_i64().unwrap_or_default(),
+                        column_number: frame["columnNumber"].as_i64().unwrap_or_default(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            message,
+            class_name: exception["className"].as_str().map(str::to_string),
+            line_number: details["lineNumber"].as_i64().unwrap_or_default(),
+            column_number: details["columnNumber"].as_i64().unwrap_or_default(),
+            url: details["url"].as_str().map(str::to_string),
+            stack,
+        }
+    }
+}
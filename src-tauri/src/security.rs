@@ -0,0 +1,222 @@
+use crate::browser::{Tab, TabId};
+use crate::cdp::{recv_event, CdpEvent, CdpSession};
+use crate::events::{emit, BrowserEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+
+/// Chrome's overall verdict on a page, as shown by its lock icon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityState {
+    #[default]
+    Unknown,
+    /// Not a secure or insecure scheme, e.g. `about:blank`.
+    Neutral,
+    Insecure,
+    Secure,
+    Info,
+    /// HTTPS with a certificate error or content that ran despite one.
+    InsecureBroken,
+}
+
+impl SecurityState {
+    fn parse(value: &str) -> Self {
+        match value {
+            "neutral" => SecurityState::Neutral,
+            "insecure" => SecurityState::Insecure,
+            "secure" => SecurityState::Secure,
+            "info" => SecurityState::Info,
+            "insecure-broken" => SecurityState::InsecureBroken,
+            _ => SecurityState::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateDetails {
+    pub subject_name: String,
+    pub issuer: String,
+    /// Seconds since the Unix epoch.
+    pub valid_from: i64,
+    pub valid_to: i64,
+    pub protocol: String,
+    pub key_exchange: String,
+    pub cipher: String,
+    pub network_error: Option<String>,
+}
+
+/// A certificate error that stopped a navigation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateError {
+    pub url: String,
+    pub host: String,
+    /// Chrome's net error name, e.g. `net::ERR_CERT_DATE_INVALID`.
+    pub error_type: String,
+}
+
+/// A host the user chose to proceed to despite a certificate error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateDecision {
+    pub host: String,
+    pub url: String,
+    pub error_type: String,
+    pub decided_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TabSecurity {
+    pub state: SecurityState,
+    pub mixed_content: bool,
+    pub certificate: Option<CertificateDetails>,
+    /// Chrome's issue ids, e.g. `scheme-is-not-cryptographic` or `ran-mixed-content`.
+    pub issues: Vec<String>,
+    /// Set while the tab shows the interstitial for a blocked navigation.
+    pub certificate_error: Option<CertificateError>,
+}
+
+impl TabSecurity {
+    /// Applies a `Security.visibleSecurityStateChanged` payload.
+    pub fn update_from_visible_state(&mut self, visible: &Value) {
+        self.state = SecurityState::parse(visible["securityState"].as_str().unwrap_or_default());
+        self.issues = visible["securityStateIssueIds"]
+            .as_array()
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.mixed_content = self.issues.iter().any(|id| id.contains("mixed-content"));
+
+        let cert = &visible["certificateSecurityState"];
+        self.certificate = cert.is_object().then(|| CertificateDetails {
+            subject_name: cert["subjectName"].as_str().unwrap_or_default().to_string(),
+            issuer: cert["issuer"].as_str().unwrap_or_default().to_string(),
+            valid_from: cert["validFrom"].as_f64().unwrap_or_default() as i64,
+            valid_to: cert["validTo"].as_f64().unwrap_or_default() as i64,
+            protocol: cert["protocol"].as_str().unwrap_or_default().to_string(),
+            key_exchange: cert["keyExchange"].as_str().unwrap_or_default().to_string(),
+            cipher: cert["cipher"].as_str().unwrap_or_default().to_string(),
+            network_error: cert["certificateNetworkError"].as_str().map(str::to_string),
+        });
+
+        // A page that loaded cleanly means the interstitial is gone
+        if self.state == SecurityState::Secure {
+            self.certificate_error = None;
+        }
+    }
+}
+
+pub fn certificate_host(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(str::to_lowercase)
+}
+
+/// Tracks a tab's security state and its certificate errors.
+///
+/// Chrome hands each certificate error over as it happens, for the main frame and
+/// subresources alike. Each one is decided on its own host: errors on hosts the user
+/// proceeded to continue, all others are cancelled. A cancelled main-frame navigation
+/// is reported so the frontend can show the interstitial.
+pub async fn pump_security(
+    tab_id: TabId,
+    session: CdpSession,
+    tabs: Arc<RwLock<HashMap<TabId, Tab>>>,
+    allowed_hosts: Arc<RwLock<HashSet<String>>>,
+    mut events: broadcast::Receiver<CdpEvent>,
+) {
+    // Main-frame navigations in flight, by request id
+    let mut navigations: HashMap<String, String> = HashMap::new();
+    // Error types of the certificate errors cancelled, by URL
+    let mut cancelled: HashMap<String, String> = HashMap::new();
+
+    while let Some(event) = recv_event(&mut events).await {
+        if !event.is_for(&session) {
+            continue;
+        }
+
+        let params = &event.params;
+        let request_id = params["requestId"].as_str().unwrap_or_default();
+        match event.method.as_str() {
+            "Security.visibleSecurityStateChanged" => {
+                if let Some(tab) = tabs.write().await.get_mut(&tab_id) {
+                    tab.security
+                        .update_from_visible_state(&params["visibleSecurityState"]);
+                    emit(BrowserEvent::TabUpdated { tab: tab.clone() });
+                }
+            }
+            "Security.certificateError" => {
+                let url = params["requestURL"].as_str().unwrap_or_default();
+                let host = certificate_host(url).unwrap_or_default();
+                let allowed = allowed_hosts.read().await.contains(&host);
+
+                let action = if allowed { "continue" } else { "cancel" };
+                if let Err(e) = session
+                    .send(
+                        "Security.handleCertificateError",
+                        json!({ "eventId": params["eventId"], "action": action }),
+                    )
+                    .await
+                {
+                    warn!(
+                        "Failed to answer certificate error in tab {}: {}",
+                        tab_id, e
+                    );
+                }
+                if allowed {
+                    debug!("Proceeding past certificate error on {}", host);
+                } else {
+                    let error_type = params["errorType"].as_str().unwrap_or_default();
+                    cancelled.insert(url.to_string(), error_type.to_string());
+                }
+            }
+            // A page target's main frame shares its id
+            "Network.requestWillBeSent"
+                if params["type"] == "Document" && params["frameId"] == session.target_id =>
+            {
+                let url = params["request"]["url"].as_str().unwrap_or_default();
+                navigations.insert(request_id.to_string(), url.to_string());
+            }
+            "Network.loadingFinished" => {
+                navigations.remove(request_id);
+            }
+            "Network.loadingFailed" => {
+                let Some(url) = navigations.remove(request_id) else {
+                    continue;
+                };
+                let error_text = params["errorText"].as_str().unwrap_or_default();
+                let Some(error_type) = cancelled.remove(&url).or_else(|| {
+                    error_text
+                        .starts_with("net::ERR_CERT_")
+                        .then(|| error_text.to_string())
+                }) else {
+                    continue;
+                };
+
+                let error = CertificateError {
+                    host: certificate_host(&url).unwrap_or_default(),
+                    url,
+                    error_type,
+                };
+                info!(
+                    "Blocked {} in tab {}: {}",
+                    error.url, tab_id, error.error_type
+                );
+
+                if let Some(tab) = tabs.write().await.get_mut(&tab_id) {
+                    tab.security.state = SecurityState::InsecureBroken;
+                    tab.security.certificate_error = Some(error.clone());
+                    emit(BrowserEvent::TabUpdated { tab: tab.clone() });
+                }
+                emit(BrowserEvent::CertificateError {
+                    tab_id: tab_id.clone(),
+                    error,
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A kind of per-origin data `Storage.clearDataForOrigin` can remove.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteDataType {
+    Cookies,
+    LocalStorage,
+    IndexedDb,
+    CacheStorage,
+    ServiceWorkers,
+}
+
+impl SiteDataType {
+    pub const ALL: [SiteDataType; 5] = [
+        SiteDataType::Cookies,
+        SiteDataType::LocalStorage,
+        SiteDataType::IndexedDb,
+        SiteDataType::CacheStorage,
+        SiteDataType::ServiceWorkers,
+    ];
+
+    fn as_cdp(&self) -> &'static str {
+        match self {
+            SiteDataType::Cookies => "cookies",
+            SiteDataType::LocalStorage => "local_storage",
+            SiteDataType::IndexedDb => "indexeddb",
+            SiteDataType::CacheStorage => "cache_storage",
+            SiteDataType::ServiceWorkers => "service_workers",
+        }
+    }
+}
+
+/// The comma-separated `storageTypes` value for `Storage.clearDataForOrigin`.
+pub fn storage_types_param(types: &[SiteDataType]) -> String {
+    types
+        .iter()
+        .map(SiteDataType::as_cdp)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteData {
+    pub origin: String,
+    pub cookie_count: usize,
+    /// Bytes used across all storage types.
+    pub usage: u64,
+    pub quota: u64,
+    /// Bytes per storage type, e.g. `indexeddb` or `cache_storage`, leaving out empty ones.
+    pub usage_breakdown: BTreeMap<String, u64>,
+    /// Chrome couldn't report the storage in use, so only `cookie_count` is known.
+    pub usage_unavailable: bool,
+}
+
+impl SiteData {
+    pub fn from_usage(origin: String, cookie_count: usize, result: &Value) -> Self {
+        let usage_breakdown = result["usageBreakdown"]
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|e| {
+                        let usage = e["usage"].as_f64()? as u64;
+                        let storage_type = e["storageType"].as_str()?;
+                        (usage > 0).then(|| (storage_type.to_string(), usage))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            origin,
+            cookie_count,
+            usage: result["usage"].as_f64().unwrap_or_default() as u64,
+            quota: result["quota"].as_f64().unwrap_or_default() as u64,
+            usage_breakdown,
+            usage_unavailable: false,
+        }
+    }
+
+    /// An origin whose storage usage couldn't be read.
+    pub fn without_usage(origin: String, cookie_count: usize) -> Self {
+        Self {
+            origin,
+            cookie_count,
+            usage: 0,
+            quota: 0,
+            usage_breakdown: BTreeMap::new(),
+            usage_unavailable: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookie_count == 0 && self.usage == 0 && !self.usage_unavailable
+    }
+}
+
+/// Normalizes a URL or bare host to the web origin its data is stored under.
+pub fn site_origin(site: &str) -> Option<String> {
+    let parsed = url::Url::parse(site)
+        .ok()
+        .filter(|u| u.has_host())
+        .or_else(|| url::Url::parse(&format!("https://{}", site)).ok())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(parsed.origin().ascii_serialization())
+}
+
+/// Counts cookies per origin from a `Storage.getCookies` result.
+///
+/// The scheme and port come from where each cookie was set, falling back to its
+/// `secure` flag when Chrome didn't record the scheme.
+pub fn cookie_origins(result: &Value) -> BTreeMap<String, usize> {
+    let mut origins = BTreeMap::new();
+    for cookie in result["cookies"].as_array().into_iter().flatten() {
+        let domain = cookie["domain"].as_str().unwrap_or_default();
+        let host = domain.trim_start_matches('.');
+        if host.is_empty() {
+            continue;
+        }
+
+        let secure = match cookie["sourceScheme"].as_str() {
+            Some("Secure") => true,
+            Some("NonSecure") => false,
+            _ => cookie["secure"].as_bool().unwrap_or(false),
+        };
+        let scheme = if secure { "https" } else { "http" };
+        let site = match cookie["sourcePort"].as_i64().filter(|port| *port > 0) {
+            Some(port) => format!("{}://{}:{}", scheme, host, port),
+            None => format!("{}://{}", scheme, host),
+        };
+        // Drops the port again when it's the scheme's default
+        let Some(origin) = site_origin(&site) else {
+            continue;
+        };
+        *origins.entry(origin).or_insert(0) += 1;
+    }
+    origins
+}
@@ -1,15 +1,34 @@
+use crate::activation::ActivationPolicy;
+use crate::browser::{LifecycleState, Tab};
 use crate::db::Database;
-use crate::error::Result;
+use crate::error::{BrowserError, Result};
+use crate::groups::{GroupColor, TabGroup};
+use crate::popups::{PopupPolicy, PopupRule};
+use crate::proxy::{ProxyConfig, ProxyCredentials};
+use crate::restore::{NamedSession, SessionTab};
+use crate::rules::{RequestRule, RuleAction};
+use crate::security::{CertificateDecision, CertificateError, TabSecurity};
+use crate::windows::{BrowserWindow, WindowId};
+use crate::zoom::ZoomLevel;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub theme: String,
     pub default_search_engine: String,
     pub ai_provider: String,
     pub ai_model: String,
     pub agent_mode_enabled: bool,
+    pub tab_lifecycle_enabled: bool,
+    pub tab_freeze_after_minutes: u64,
+    pub tab_discard_after_minutes: u64,
+    pub proxy: ProxyConfig,
+    /// Reopen the tabs from last time at startup.
+    pub restore_session: bool,
+    /// Which tab to switch to when the active tab is closed.
+    pub tab_close_activation: ActivationPolicy,
 }
 
 impl Default for Settings {
@@ -20,6 +39,12 @@ impl Default for Settings {
             ai_provider: "openai".to_string(),
             ai_model: "gpt-4o".to_string(),
             agent_mode_enabled: false,
+            tab_lifecycle_enabled: true,
+            tab_freeze_after_minutes: 5,
+            tab_discard_after_minutes: 30,
+            proxy: ProxyConfig::default(),
+            restore_session: true,
+            tab_close_activation: ActivationPolicy::default(),
         }
     }
 }
@@ -40,8 +65,20 @@ pub async fn get_settings(db: &Database) -> Result<Settings> {
             "default_search_engine" => settings.default_search_engine = value,
             "ai_provider" => settings.ai_provider = value,
             "ai_model" => settings.ai_model = value,
-            "agent_mode_enabled" => {
-                settings.agent_mode_enabled = value.parse().unwrap_or(false)
+            "agent_mode_enabled" => settings.agent_mode_enabled = value.parse().unwrap_or(false),
+            "tab_lifecycle_enabled" => {
+                settings.tab_lifecycle_enabled = value.parse().unwrap_or(true)
+            }
+            "tab_freeze_after_minutes" => {
+                settings.tab_freeze_after_minutes = value.parse().unwrap_or(5)
+            }
+            "tab_discard_after_minutes" => {
+                settings.tab_discard_after_minutes = value.parse().unwrap_or(30)
+            }
+            "proxy" => settings.proxy = serde_json::from_str(&value).unwrap_or_default(),
+            "restore_session" => settings.restore_session = value.parse().unwrap_or(true),
+            "tab_close_activation" => {
+                settings.tab_close_activation = ActivationPolicy::parse(&value)
             }
             _ => {}
         }
@@ -83,6 +120,42 @@ pub async fn update_settings(db: &Database, settings: &Settings) -> Result<()> {
         .execute(&mut *tx)
         .await?;
 
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("tab_lifecycle_enabled")
+        .bind(settings.tab_lifecycle_enabled.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("tab_freeze_after_minutes")
+        .bind(settings.tab_freeze_after_minutes.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("tab_discard_after_minutes")
+        .bind(settings.tab_discard_after_minutes.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("proxy")
+        .bind(serde_json::to_string(&settings.proxy)?)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("restore_session")
+        .bind(settings.restore_session.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind("tab_close_activation")
+        .bind(settings.tab_close_activation.as_str())
+        .execute(&mut *tx)
+        .await?;
+
     tx.commit().await?;
     Ok(())
 }
@@ -102,7 +175,10 @@ pub struct Conversation {
     pub messages: Vec<ConversationMessage>,
 }
 
-pub async fn get_conversation(db: &Database, conversation_id: &str) -> Result<Option<Conversation>> {
+pub async fn get_conversation(
+    db: &Database,
+    conversation_id: &str,
+) -> Result<Option<Conversation>> {
     let row = sqlx::query(
         "SELECT id, tab_id, model_provider, messages FROM agent_conversations WHERE id = ?1",
     )
@@ -169,9 +245,12 @@ pub async fn add_message_to_conversation(
 ) -> Result<()> {
     let mut conversation = get_conversation(db, conversation_id)
         .await?
-        .ok_or_else(|| crate::error::BrowserError::NotFound(
-            format!("Conversation {} not found", conversation_id)
-        ))?;
+        .ok_or_else(|| {
+            crate::error::BrowserError::NotFound(format!(
+                "Conversation {} not found",
+                conversation_id
+            ))
+        })?;
 
     conversation.messages.push(ConversationMessage {
         role: role.to_string(),
@@ -193,11 +272,7 @@ pub struct HistoryEntry {
     pub last_visit: String,
 }
 
-pub async fn add_history_entry(
-    db: &Database,
-    url: &str,
-    title: Option<&str>,
-) -> Result<()> {
+pub async fn add_history_entry(db: &Database, url: &str, title: Option<&str>) -> Result<()> {
     // Check if URL already exists
     let existing = sqlx::query("SELECT id, visit_count FROM history WHERE url = ?1")
         .bind(url)
@@ -251,6 +326,26 @@ pub async fn get_history(db: &Database) -> Result<Vec<HistoryEntry>> {
     Ok(history)
 }
 
+/// Returns every history entry, for building the omnibox index.
+pub async fn get_all_history(db: &Database) -> Result<Vec<HistoryEntry>> {
+    let rows = sqlx::query("SELECT id, url, title, visit_count, last_visit FROM history")
+        .fetch_all(db)
+        .await?;
+
+    let history = rows
+        .into_iter()
+        .map(|row| HistoryEntry {
+            id: row.get("id"),
+            url: row.get("url"),
+            title: row.get("title"),
+            visit_count: row.get("visit_count"),
+            last_visit: row.get("last_visit"),
+        })
+        .collect();
+
+    Ok(history)
+}
+
 pub async fn clear_history(db: &Database) -> Result<()> {
     sqlx::query("DELETE FROM history").execute(db).await?;
     Ok(())
@@ -272,15 +367,13 @@ pub async fn add_bookmark(
     title: &str,
     folder_id: Option<i64>,
 ) -> Result<Bookmark> {
-    let id = sqlx::query(
-        "INSERT INTO bookmarks (url, title, folder_id) VALUES (?1, ?2, ?3)"
-    )
-    .bind(url)
-    .bind(title)
-    .bind(folder_id)
-    .execute(db)
-    .await?
-    .last_insert_rowid();
+    let id = sqlx::query("INSERT INTO bookmarks (url, title, folder_id) VALUES (?1, ?2, ?3)")
+        .bind(url)
+        .bind(title)
+        .bind(folder_id)
+        .execute(db)
+        .await?
+        .last_insert_rowid();
 
     Ok(Bookmark {
         id,
@@ -293,7 +386,7 @@ pub async fn add_bookmark(
 
 pub async fn get_bookmarks(db: &Database) -> Result<Vec<Bookmark>> {
     let rows = sqlx::query(
-        "SELECT id, url, title, folder_id, created_at FROM bookmarks ORDER BY created_at DESC"
+        "SELECT id, url, title, folder_id, created_at FROM bookmarks ORDER BY created_at DESC",
     )
     .fetch_all(db)
     .await?;
@@ -319,3 +412,499 @@ pub async fn delete_bookmark(db: &Database, id: i64) -> Result<()> {
         .await?;
     Ok(())
 }
+
+// Upload permission functions
+pub async fn add_upload_permission(db: &Database, path: &str) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO upload_permissions (path) VALUES (?1)")
+        .bind(path)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_upload_permissions(db: &Database) -> Result<Vec<String>> {
+    let rows = sqlx::query("SELECT path FROM upload_permissions ORDER BY path")
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows.into_iter().map(|row| row.get("path")).collect())
+}
+
+pub async fn remove_upload_permission(db: &Database, path: &str) -> Result<()> {
+    sqlx::query("DELETE FROM upload_permissions WHERE path = ?1")
+        .bind(path)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+// Popup policy functions
+pub async fn get_popup_rules(db: &Database) -> Result<Vec<PopupRule>> {
+    let rows = sqlx::query("SELECT host, policy FROM popup_policies ORDER BY host")
+        .fetch_all(db)
+        .await?;
+
+    let rules = rows
+        .into_iter()
+        .map(|row| PopupRule {
+            host: row.get("host"),
+            policy: PopupPolicy::parse(row.get("policy")),
+        })
+        .collect();
+
+    Ok(rules)
+}
+
+pub async fn set_popup_rule(db: &Database, host: &str, policy: PopupPolicy) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO popup_policies (host, policy) VALUES (?1, ?2)")
+        .bind(host)
+        .bind(policy.as_str())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_popup_rule(db: &Database, host: &str) -> Result<()> {
+    sqlx::query("DELETE FROM popup_policies WHERE host = ?1")
+        .bind(host)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+// Request rule functions
+pub async fn get_request_rules(db: &Database) -> Result<Vec<RequestRule>> {
+    let rows = sqlx::query(
+        "SELECT id, name, url_pattern, resource_type, action, enabled FROM request_rules ORDER BY id",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut rules = Vec::with_capacity(rows.len());
+    for row in rows {
+        let action: String = row.get("action");
+        rules.push(RequestRule {
+            id: row.get("id"),
+            name: row.get("name"),
+            url_pattern: row.get("url_pattern"),
+            resource_type: row.get("resource_type"),
+            action: serde_json::from_str(&action)?,
+            enabled: row.get("enabled"),
+        });
+    }
+
+    Ok(rules)
+}
+
+pub async fn add_request_rule(
+    db: &Database,
+    name: &str,
+    url_pattern: &str,
+    resource_type: Option<&str>,
+    action: &RuleAction,
+) -> Result<RequestRule> {
+    let id = sqlx::query(
+        "INSERT INTO request_rules (name, url_pattern, resource_type, action) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(name)
+    .bind(url_pattern)
+    .bind(resource_type)
+    .bind(serde_json::to_string(action)?)
+    .execute(db)
+    .await?
+    .last_insert_rowid();
+
+    Ok(RequestRule {
+        id,
+        name: name.to_string(),
+        url_pattern: url_pattern.to_string(),
+        resource_type: resource_type.map(str::to_string),
+        action: action.clone(),
+        enabled: true,
+    })
+}
+
+pub async fn set_request_rule_enabled(db: &Database, id: i64, enabled: bool) -> Result<()> {
+    sqlx::query("UPDATE request_rules SET enabled = ?1 WHERE id = ?2")
+        .bind(enabled)
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_request_rule(db: &Database, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM request_rules WHERE id = ?1")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+// Proxy credential functions
+pub async fn get_proxy_credentials(db: &Database) -> Result<Option<ProxyCredentials>> {
+    let row = sqlx::query("SELECT username, password FROM proxy_credentials WHERE id = 1")
+        .fetch_optional(db)
+        .await?;
+
+    Ok(row.map(|row| ProxyCredentials {
+        username: row.get("username"),
+        password: row.get("password"),
+    }))
+}
+
+pub async fn set_proxy_credentials(
+    db: &Database,
+    credentials: Option<&ProxyCredentials>,
+) -> Result<()> {
+    match credentials {
+        Some(credentials) => {
+            sqlx::query(
+                "INSERT OR REPLACE INTO proxy_credentials (id, username, password) VALUES (1, ?1, ?2)",
+            )
+            .bind(&credentials.username)
+            .bind(&credentials.password)
+            .execute(db)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM proxy_credentials")
+                .execute(db)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// Zoom level functions
+pub async fn get_zoom_levels(db: &Database) -> Result<Vec<ZoomLevel>> {
+    let rows = sqlx::query("SELECT origin, level FROM zoom_levels ORDER BY origin")
+        .fetch_all(db)
+        .await?;
+
+    let levels = rows
+        .into_iter()
+        .map(|row| ZoomLevel {
+            origin: row.get("origin"),
+            level: row.get("level"),
+        })
+        .collect();
+
+    Ok(levels)
+}
+
+pub async fn set_zoom_level(db: &Database, origin: &str, level: f64) -> Result<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO zoom_levels (origin, level, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+    )
+    .bind(origin)
+    .bind(level)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_zoom_level(db: &Database, origin: &str) -> Result<()> {
+    sqlx::query("DELETE FROM zoom_levels WHERE origin = ?1")
+        .bind(origin)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+// Certificate decision functions
+pub async fn record_certificate_decision(db: &Database, error: &CertificateError) -> Result<()> {
+    sqlx::query("INSERT INTO certificate_decisions (host, url, error_type) VALUES (?1, ?2, ?3)")
+        .bind(&error.host)
+        .bind(&error.url)
+        .bind(&error.error_type)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_certificate_decisions(db: &Database) -> Result<Vec<CertificateDecision>> {
+    let rows = sqlx::query(
+        "SELECT host, url, error_type, decided_at FROM certificate_decisions ORDER BY decided_at DESC",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let decisions = rows
+        .into_iter()
+        .map(|row| CertificateDecision {
+            host: row.get("host"),
+            url: row.get("url"),
+            error_type: row.get("error_type"),
+            decided_at: row.get("decided_at"),
+        })
+        .collect();
+
+    Ok(decisions)
+}
+
+// Open tab functions
+/// Writes the current tab set, replacing whatever was saved before.
+///
+/// Runs as one transaction so a crash mid-write leaves the previous set intact.
+/// Tabs in private browser contexts are never written.
+pub async fn save_tabs(
+    db: &Database,
+    tabs: &[Tab],
+    groups: &[TabGroup],
+    windows: &[BrowserWindow],
+) -> Result<()> {
+    let tabs: Vec<&Tab> = tabs
+        .iter()
+        .filter(|t| t.browser_context_id.is_none())
+        .collect();
+    let mut tx = db.begin().await?;
+
+    for tab in &tabs {
+        sqlx::query(
+            r#"
+            INSERT INTO tabs (
+                id, url, title, favicon, position, is_active, group_id, pinned, window_id,
+                updated_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, CURRENT_TIMESTAMP)
+            ON CONFLICT(id) DO UPDATE SET
+                url = excluded.url,
+                title = excluded.title,
+                favicon = excluded.favicon,
+                position = excluded.position,
+                is_active = excluded.is_active,
+                group_id = excluded.group_id,
+                pinned = excluded.pinned,
+                window_id = excluded.window_id,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&tab.id)
+        .bind(&tab.url)
+        .bind(&tab.title)
+        .bind(&tab.favicon)
+        .bind(tab.position)
+        .bind(tab.is_active)
+        .bind(&tab.group_id)
+        .bind(tab.pinned)
+        .bind(&tab.window_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Nothing references groups by key, so they can simply be rewritten
+    sqlx::query("DELETE FROM tab_groups")
+        .execute(&mut *tx)
+        .await?;
+    for group in groups
+        .iter()
+        .filter(|g| tabs.iter().any(|t| t.group_id.as_ref() == Some(&g.id)))
+    {
+        sqlx::query("INSERT INTO tab_groups (id, title, color, collapsed) VALUES (?1, ?2, ?3, ?4)")
+            .bind(&group.id)
+            .bind(&group.title)
+            .bind(group.color.as_str())
+            .bind(group.collapsed)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM windows").execute(&mut *tx).await?;
+    for (position, window) in windows
+        .iter()
+        .filter(|w| tabs.iter().any(|t| t.window_id == w.id))
+        .enumerate()
+    {
+        sqlx::query("INSERT INTO windows (id, position) VALUES (?1, ?2)")
+            .bind(&window.id)
+            .bind(position as i64)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Upsert then prune, rather than clearing the table, so conversations keep their tab
+    let sql = if tabs.is_empty() {
+        "DELETE FROM tabs".to_string()
+    } else {
+        format!(
+            "DELETE FROM tabs WHERE id NOT IN ({})",
+            vec!["?"; tabs.len()].join(", ")
+        )
+    };
+    let mut prune = sqlx::query(&sql);
+    for tab in &tabs {
+        prune = prune.bind(&tab.id);
+    }
+    prune.execute(&mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn get_saved_tabs(db: &Database) -> Result<Vec<Tab>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, url, title, favicon, position, is_active, group_id, pinned, window_id
+        FROM tabs
+        ORDER BY position
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let tabs = rows
+        .into_iter()
+        .map(|row| Tab {
+            id: row.get("id"),
+            url: row.get("url"),
+            title: row.get("title"),
+            favicon: row.get("favicon"),
+            window_id: row.get("window_id"),
+            position: row.get("position"),
+            is_active: row.get("is_active"),
+            lifecycle_state: LifecycleState::Active,
+            opener_id: None,
+            browser_context_id: None,
+            group_id: row.get("group_id"),
+            pinned: row.get("pinned"),
+            security: TabSecurity::default(),
+        })
+        .collect();
+
+    Ok(tabs)
+}
+
+pub async fn get_saved_tab_groups(db: &Database) -> Result<Vec<TabGroup>> {
+    let rows = sqlx::query("SELECT id, title, color, collapsed FROM tab_groups")
+        .fetch_all(db)
+        .await?;
+
+    let groups = rows
+        .into_iter()
+        .map(|row| TabGroup {
+            id: row.get("id"),
+            title: row.get("title"),
+            color: GroupColor::parse(row.get("color")),
+            collapsed: row.get("collapsed"),
+        })
+        .collect();
+
+    Ok(groups)
+}
+
+/// Returns the saved windows in the order they were opened.
+pub async fn get_saved_windows(db: &Database) -> Result<Vec<WindowId>> {
+    let rows = sqlx::query("SELECT id FROM windows ORDER BY position")
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows.into_iter().map(|row| row.get("id")).collect())
+}
+
+// Named session functions
+/// Saves tabs under `name`, replacing the tabs of any session already called that.
+pub async fn save_named_session(db: &Database, name: &str, tabs: &[Tab]) -> Result<NamedSession> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO named_sessions (name) VALUES (?1)
+        ON CONFLICT(name) DO UPDATE SET updated_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(name)
+    .execute(&mut *tx)
+    .await?;
+    let id: i64 = sqlx::query("SELECT id FROM named_sessions WHERE name = ?1")
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+
+    sqlx::query("DELETE FROM named_session_tabs WHERE session_id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (position, tab) in tabs.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO named_session_tabs (session_id, url, title, favicon, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(id)
+        .bind(&tab.url)
+        .bind(&tab.title)
+        .bind(&tab.favicon)
+        .bind(position as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    get_named_sessions(db)
+        .await?
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| BrowserError::NotFound(format!("Session {} not found", name)))
+}
+
+pub async fn get_named_sessions(db: &Database) -> Result<Vec<NamedSession>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT s.id, s.name, s.created_at, s.updated_at, COUNT(t.id) AS tab_count
+        FROM named_sessions s
+        LEFT JOIN named_session_tabs t ON t.session_id = s.id
+        GROUP BY s.id
+        ORDER BY s.updated_at DESC
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let sessions = rows
+        .into_iter()
+        .map(|row| NamedSession {
+            id: row.get("id"),
+            name: row.get("name"),
+            tab_count: row.get("tab_count"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+        .collect();
+
+    Ok(sessions)
+}
+
+pub async fn get_named_session_tabs(db: &Database, session_id: i64) -> Result<Vec<SessionTab>> {
+    let rows = sqlx::query(
+        "SELECT url, title, favicon FROM named_session_tabs WHERE session_id = ?1 ORDER BY position",
+    )
+    .bind(session_id)
+    .fetch_all(db)
+    .await?;
+
+    let tabs = rows
+        .into_iter()
+        .map(|row| SessionTab {
+            url: row.get("url"),
+            title: row.get("title"),
+            favicon: row.get("favicon"),
+        })
+        .collect();
+
+    Ok(tabs)
+}
+
+pub async fn delete_named_session(db: &Database, session_id: i64) -> Result<()> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM named_session_tabs WHERE session_id = ?1")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM named_sessions WHERE id = ?1")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
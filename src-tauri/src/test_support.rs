@@ -0,0 +1,193 @@
+//! An in-process fake of Chrome's DevTools WebSocket endpoint, so `BrowserManager`
+//! can be driven by `cargo test` without a browser.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+type Handler = Box<dyn Fn(&Value) -> Result<Value, String> + Send + Sync>;
+
+/// A scripted DevTools server.
+///
+/// It also answers `GET /json/version` like Chrome does, for discovering the WebSocket
+/// URL. Every request is recorded. Methods without a handler get an empty result, except
+/// `Target.createTarget` and `Target.attachToTarget`, which hand out fresh ids so tabs
+/// can attach.
+pub struct MockCdpServer {
+    addr: SocketAddr,
+    handlers: Arc<Mutex<HashMap<String, Handler>>>,
+    requests: Arc<Mutex<Vec<Value>>>,
+    clients: Arc<Mutex<Vec<mpsc::UnboundedSender<Message>>>>,
+}
+
+impl MockCdpServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock CDP server");
+        let addr = listener.local_addr().expect("mock CDP server address");
+
+        let server = Self {
+            addr,
+            handlers: Arc::new(Mutex::new(HashMap::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            clients: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let handlers = server.handlers.clone();
+        let requests = server.requests.clone();
+        let clients = server.clients.clone();
+        let next_target = Arc::new(AtomicU64::new(1));
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut head = [0; 17];
+                let peeked = stream.peek(&mut head).await.unwrap_or_default();
+                if head[..peeked] == *b"GET /json/version" {
+                    tokio::spawn(answer_version(stream, addr));
+                    continue;
+                }
+
+                let Ok(ws) = accept_async(stream).await else {
+                    continue;
+                };
+                let (mut sink, mut source) = ws.split();
+
+                let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+                clients.lock().unwrap().push(tx.clone());
+                tokio::spawn(async move {
+                    while let Some(message) = rx.recv().await {
+                        if sink.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                let handlers = handlers.clone();
+                let requests = requests.clone();
+                let next_target = next_target.clone();
+                tokio::spawn(async move {
+                    while let Some(Ok(message)) = source.next().await {
+                        let Message::Text(text) = message else {
+                            continue;
+                        };
+                        let request: Value = serde_json::from_str(&text).expect("CDP request JSON");
+                        requests.lock().unwrap().push(request.clone());
+
+                        let method = request["method"].as_str().unwrap_or_default();
+                        let params = &request["params"];
+                        let outcome = match handlers.lock().unwrap().get(method) {
+                            Some(handler) => handler(params),
+                            None => Ok(default_result(method, params, &next_target)),
+                        };
+
+                        let mut response = match outcome {
+                            Ok(result) => json!({ "id": request["id"], "result": result }),
+                            Err(message) => json!({
+                                "id": request["id"],
+                                "error": { "code": -32000, "message": message },
+                            }),
+                        };
+                        if let Some(session_id) = request.get("sessionId") {
+                            response["sessionId"] = session_id.clone();
+                        }
+                        let _ = tx.send(Message::Text(response.to_string()));
+                    }
+                });
+            }
+        });
+
+        server
+    }
+
+    pub fn ws_url(&self) -> String {
+        ws_url(self.addr)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Scripts the reply to `method`. Returning `Err` sends a CDP error response.
+    pub fn on<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(&Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.handlers
+            .lock()
+            .unwrap()
+            .insert(method.to_string(), Box::new(handler));
+    }
+
+    /// Pushes an event to every connected client.
+    pub fn emit(&self, method: &str, params: Value, session_id: Option<&str>) {
+        let mut event = json!({ "method": method, "params": params });
+        if let Some(session_id) = session_id {
+            event["sessionId"] = json!(session_id);
+        }
+        for client in self.clients.lock().unwrap().iter() {
+            let _ = client.send(Message::Text(event.to_string()));
+        }
+    }
+
+    /// Every request received for `method`, oldest first.
+    pub fn requests(&self, method: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r["method"] == method)
+            .cloned()
+            .collect()
+    }
+
+    /// Waits up to a second for a request to `method`, for work done on background tasks.
+    pub async fn wait_for_request(&self, method: &str) -> Option<Value> {
+        for _ in 0..100 {
+            if let Some(request) = self.requests(method).pop() {
+                return Some(request);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        None
+    }
+}
+
+fn ws_url(addr: SocketAddr) -> String {
+    format!("ws://{}/devtools/browser/mock", addr)
+}
+
+async fn answer_version(mut stream: TcpStream, addr: SocketAddr) {
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request).await;
+
+    let body = json!({ "Browser": "Mock/1.0", "webSocketDebuggerUrl": ws_url(addr) }).to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+fn default_result(method: &str, params: &Value, next_target: &AtomicU64) -> Value {
+    match method {
+        "Target.createTarget" => {
+            let n = next_target.fetch_add(1, Ordering::Relaxed);
+            json!({ "targetId": format!("target-{}", n) })
+        }
+        "Target.attachToTarget" => {
+            let target_id = params["targetId"].as_str().unwrap_or_default();
+            json!({ "sessionId": format!("session-{}", target_id) })
+        }
+        _ => json!({}),
+    }
+}
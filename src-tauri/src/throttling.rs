@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Network conditions applied to a tab with `Network.emulateNetworkConditions`.
+///
+/// The 3G presets also slow the CPU the way DevTools' low-end and mid-tier mobile
+/// presets do, so a page behaves like it would on the phones that have those networks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "preset", rename_all = "snake_case")]
+pub enum NetworkEmulation {
+    None,
+    Offline,
+    #[serde(rename = "slow_3g")]
+    Slow3G,
+    #[serde(rename = "fast_3g")]
+    Fast3G,
+    Custom {
+        latency_ms: f64,
+        /// Bytes per second; negative disables throttling.
+        download_throughput: f64,
+        /// Bytes per second; negative disables throttling.
+        upload_throughput: f64,
+        /// How many times slower than normal the CPU runs; 1 for full speed.
+        #[serde(default = "full_speed")]
+        cpu_slowdown: f64,
+    },
+}
+
+fn full_speed() -> f64 {
+    1.0
+}
+
+impl NetworkEmulation {
+    /// `(offline, latency_ms, download_bps, upload_bps)`, using the same numbers as
+    /// the DevTools throttling presets.
+    fn parameters(&self) -> (bool, f64, f64, f64) {
+        match *self {
+            NetworkEmulation::None => (false, 0.0, -1.0, -1.0),
+            NetworkEmulation::Offline => (true, 0.0, -1.0, -1.0),
+            NetworkEmulation::Slow3G => (false, 2000.0, 50_000.0, 50_000.0),
+            NetworkEmulation::Fast3G => (false, 562.5, 180_000.0, 84_375.0),
+            NetworkEmulation::Custom {
+                latency_ms,
+                download_throughput,
+                upload_throughput,
+                ..
+            } => (
+                false,
+                latency_ms.max(0.0),
+                download_throughput,
+                upload_throughput,
+            ),
+        }
+    }
+
+    pub fn cpu_slowdown(&self) -> f64 {
+        match *self {
+            NetworkEmulation::None | NetworkEmulation::Offline => 1.0,
+            NetworkEmulation::Slow3G => 6.0,
+            NetworkEmulation::Fast3G => 4.0,
+            NetworkEmulation::Custom { cpu_slowdown, .. } => cpu_slowdown.max(1.0),
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(self, NetworkEmulation::Offline)
+    }
+
+    pub fn to_cdp_params(&self) -> Value {
+        let (offline, latency, download, upload) = self.parameters();
+        json!({
+            "offline": offline,
+            "latency": latency,
+            "downloadThroughput": download,
+            "uploadThroughput": upload,
+        })
+    }
+
+    /// Parameters for `Emulation.setCPUThrottlingRate`.
+    pub fn to_cpu_throttling_params(&self) -> Value {
+        json!({ "rate": self.cpu_slowdown() })
+    }
+}
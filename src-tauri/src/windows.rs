@@ -0,0 +1,87 @@
+use crate::browser::{get_browser_manager, TabId};
+use crate::events::{subscribe, BrowserEvent};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+/// Identifies a browser window. It doubles as the label of its Tauri window.
+pub type WindowId = String;
+
+/// The window from `tauri.conf.json`. It is always open and takes in tabs whose window
+/// is gone.
+pub const MAIN_WINDOW: &str = "main";
+
+/// A browser window with its own tab strip and active tab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserWindow {
+    pub id: WindowId,
+    pub active_tab: Option<TabId>,
+    pub tab_count: usize,
+}
+
+pub fn new_window_id() -> WindowId {
+    format!("window-{}", uuid::Uuid::new_v4())
+}
+
+/// Opens and closes Tauri windows as browser windows come and go.
+pub fn start_window_sync(app_handle: AppHandle) {
+    // Subscribe before spawning so windows restored during setup aren't missed
+    let mut events = subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(BrowserEvent::WindowCreated { window_id }) => {
+                    open_window(&app_handle, &window_id)
+                }
+                Ok(BrowserEvent::WindowClosed { window_id }) => {
+                    if let Some(window) = app_handle.get_webview_window(&window_id) {
+                        let _ = window.close();
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+fn open_window(app_handle: &AppHandle, window_id: &str) {
+    if window_id == MAIN_WINDOW || app_handle.get_webview_window(window_id).is_some() {
+        return;
+    }
+
+    let built =
+        WebviewWindowBuilder::new(app_handle, window_id, WebviewUrl::App("index.html".into()))
+            .title("Aether")
+            .inner_size(1280.0, 800.0)
+            .min_inner_size(800.0, 600.0)
+            .build();
+    match built {
+        Ok(_) => info!("Opened window {}", window_id),
+        Err(e) => warn!("Failed to open window {}: {}", window_id, e),
+    }
+}
+
+/// Keeps the manager's windows in step with what the user does to the Tauri windows.
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    let window_id = window.label().to_string();
+    match event {
+        WindowEvent::Focused(true) => {
+            tauri::async_runtime::spawn(async move {
+                if let Some(manager) = get_browser_manager() {
+                    manager.lock().await.focus_window(&window_id).await;
+                }
+            });
+        }
+        // Closing a window closes its tabs; it may already be gone if its last tab closed
+        WindowEvent::Destroyed if window_id != MAIN_WINDOW => {
+            tauri::async_runtime::spawn(async move {
+                if let Some(manager) = get_browser_manager() {
+                    let _ = manager.lock().await.close_window(&window_id).await;
+                }
+            });
+        }
+        _ => {}
+    }
+}
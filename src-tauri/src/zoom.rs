@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Zoom steps, matching Chrome's own presets.
+pub const ZOOM_LEVELS: &[f64] = &[
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0,
+];
+
+pub const DEFAULT_ZOOM: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoomAction {
+    In,
+    Out,
+    Reset,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoomLevel {
+    pub origin: String,
+    pub level: f64,
+}
+
+/// Returns the origin zoom is remembered for, e.g. `https://example.com:8443`.
+///
+/// Only web pages have one; `about:` and `chrome:` pages always show at 100%.
+pub fn zoom_origin(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(url.origin().ascii_serialization())
+}
+
+/// The level remembered for the site at `url`, by origin.
+pub fn zoom_for_url(levels: &HashMap<String, f64>, url: &str) -> f64 {
+    zoom_origin(url)
+        .and_then(|origin| levels.get(&origin).copied())
+        .unwrap_or(DEFAULT_ZOOM)
+}
+
+/// The level one step in or out from `current`, or the default on reset.
+pub fn next_zoom_level(current: f64, action: ZoomAction) -> f64 {
+    match action {
+        ZoomAction::Reset => DEFAULT_ZOOM,
+        ZoomAction::In => ZOOM_LEVELS
+            .iter()
+            .copied()
+            .find(|level| *level > current + f64::EPSILON)
+            .unwrap_or(ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]),
+        ZoomAction::Out => ZOOM_LEVELS
+            .iter()
+            .rev()
+            .copied()
+            .find(|level| *level < current - f64::EPSILON)
+            .unwrap_or(ZOOM_LEVELS[0]),
+    }
+}
@@ -19,7 +19,10 @@ function App() {
     refreshTabs,
     createTab,
     closeTab,
+    reopenClosedTab,
     switchTab,
+    switchToRecentTab,
+    createWindow,
     toggleAgentPanel,
   } = useBrowserStore();
   const { loadSettings } = useSettingsStore();
@@ -39,6 +42,13 @@ function App() {
         return;
       }
 
+      // Cmd/Ctrl + N: New window
+      if (modKey && e.key === "n" && !e.shiftKey) {
+        e.preventDefault();
+        createWindow();
+        return;
+      }
+
       // Cmd/Ctrl + W: Close tab
       if (modKey && e.key === "w" && !e.shiftKey) {
         e.preventDefault();
@@ -48,6 +58,20 @@ function App() {
         return;
       }
 
+      // Cmd/Ctrl + Shift + T: Reopen the last closed tab
+      if (modKey && e.shiftKey && e.key.toLowerCase() === "t") {
+        e.preventDefault();
+        reopenClosedTab();
+        return;
+      }
+
+      // Ctrl + Tab: Switch to the previously used tab
+      if (e.ctrlKey && e.key === "Tab") {
+        e.preventDefault();
+        switchToRecentTab();
+        return;
+      }
+
       // Cmd/Ctrl + L: Focus address bar
       if (modKey && e.key === "l") {
         e.preventDefault();
@@ -110,18 +134,29 @@ function App() {
         return;
       }
     },
-    [tabs, activeTabId, createTab, closeTab, switchTab, toggleAgentPanel]
+    [
+      tabs,
+      activeTabId,
+      createTab,
+      closeTab,
+      reopenClosedTab,
+      switchTab,
+      switchToRecentTab,
+      createWindow,
+      toggleAgentPanel,
+    ]
   );
 
   useEffect(() => {
     // Initialize app
     loadSettings();
-    refreshTabs();
 
-    // Create initial tab if none exist
-    if (tabs.length === 0) {
-      createTab("about:blank");
-    }
+    // Create an initial tab unless the previous session was restored
+    refreshTabs().then(() => {
+      if (useBrowserStore.getState().tabs.length === 0) {
+        createTab("about:blank");
+      }
+    });
 
     // Add keyboard listener
     window.addEventListener("keydown", handleKeyDown);
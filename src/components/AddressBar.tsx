@@ -9,25 +9,33 @@ import {
   Search,
   Clock,
   Globe,
+  Layers,
+  ShieldAlert,
 } from "lucide-react";
 import { useBrowserStore } from "@/stores/browserStore";
 import { useBookmarkStore } from "@/stores/bookmarkStore";
 import { formatUrl, cn } from "@/lib/utils";
-import type { HistoryEntry } from "@/types";
+import type {
+  MatchRange,
+  OmniboxSuggestion,
+  SecurityState,
+  SuggestionKind,
+} from "@/types";
 import { invoke } from "@tauri-apps/api/core";
 
-interface Suggestion {
-  type: "history" | "bookmark" | "search";
-  url: string;
-  title?: string;
-  icon?: React.ReactNode;
-}
+const SUGGESTION_ICONS: Record<SuggestionKind, React.ReactNode> = {
+  tab: <Layers className="w-4 h-4 text-primary" />,
+  bookmark: <Star className="w-4 h-4 text-clay-400" />,
+  history: <Clock className="w-4 h-4 text-muted-foreground" />,
+  search: <Search className="w-4 h-4 text-primary" />,
+};
 
 export function AddressBar() {
   const {
     activeTabId,
     tabs,
     navigateTab,
+    switchTab,
     viewMode,
     setViewMode,
     goBack,
@@ -41,17 +49,11 @@ export function AddressBar() {
 
   const [inputValue, setInputValue] = useState("");
   const [isFocused, setIsFocused] = useState(false);
-  const [suggestions, setSuggestions] = useState<Suggestion[]>([]);
+  const [suggestions, setSuggestions] = useState<OmniboxSuggestion[]>([]);
   const [selectedIndex, setSelectedIndex] = useState(-1);
-  const [history, setHistory] = useState<HistoryEntry[]>([]);
 
   const isBookmarked = bookmarks.some((b) => b.url === activeTab?.url);
 
-  // Load history on mount
-  useEffect(() => {
-    invoke<HistoryEntry[]>("get_history").then(setHistory).catch(console.error);
-  }, []);
-
   // Update suggestions when input changes
   useEffect(() => {
     if (!inputValue.trim() || !isFocused) {
@@ -59,57 +61,22 @@ export function AddressBar() {
       return;
     }
 
-    const query = inputValue.toLowerCase();
-    const newSuggestions: Suggestion[] = [];
-
-    // Add bookmark suggestions
-    bookmarks
-      .filter(
-        (b) =>
-          b.title.toLowerCase().includes(query) ||
-          b.url.toLowerCase().includes(query)
-      )
-      .slice(0, 3)
-      .forEach((b) =>
-        newSuggestions.push({
-          type: "bookmark",
-          url: b.url,
-          title: b.title,
-          icon: <Star className="w-4 h-4 text-clay-400" />,
-        })
-      );
-
-    // Add history suggestions
-    history
-      .filter(
-        (h) =>
-          (h.title?.toLowerCase().includes(query) ||
-            h.url.toLowerCase().includes(query)) &&
-          !bookmarks.some((b) => b.url === h.url)
-      )
-      .slice(0, 3)
-      .forEach((h) =>
-        newSuggestions.push({
-          type: "history",
-          url: h.url,
-          title: h.title,
-          icon: <Clock className="w-4 h-4 text-muted-foreground" />,
-        })
-      );
-
-    // Add search suggestion
-    if (!inputValue.includes(".")) {
-      newSuggestions.push({
-        type: "search",
-        url: `https://google.com/search?q=${encodeURIComponent(inputValue)}`,
-        title: `Search for "${inputValue}"`,
-        icon: <Search className="w-4 h-4 text-primary" />,
-      });
-    }
-
-    setSuggestions(newSuggestions);
-    setSelectedIndex(-1);
-  }, [inputValue, history, bookmarks, isFocused]);
+    // Ignore answers for input that has since changed
+    let stale = false;
+    invoke<OmniboxSuggestion[]>("get_omnibox_suggestions", {
+      query: inputValue,
+    })
+      .then((newSuggestions) => {
+        if (!stale) {
+          setSuggestions(newSuggestions);
+          setSelectedIndex(-1);
+        }
+      })
+      .catch(console.error);
+    return () => {
+      stale = true;
+    };
+  }, [inputValue, isFocused]);
 
   // Update input when tab changes
   useEffect(() => {
@@ -153,9 +120,14 @@ export function AddressBar() {
     }
   };
 
-  const handleSelectSuggestion = (suggestion: Suggestion) => {
-    if (!activeTabId) return;
-    navigateTab(activeTabId, suggestion.url);
+  const handleSelectSuggestion = (suggestion: OmniboxSuggestion) => {
+    if (suggestion.kind === "tab" && suggestion.tab_id) {
+      switchTab(suggestion.tab_id);
+    } else if (activeTabId) {
+      navigateTab(activeTabId, suggestion.url);
+    } else {
+      return;
+    }
     setInputValue(suggestion.url);
     setIsFocused(false);
     setSuggestions([]);
@@ -218,7 +190,7 @@ export function AddressBar() {
           )}
         >
           {/* Security icon */}
-          <Lock className="w-3.5 h-3.5 text-sage-500" />
+          <SecurityIcon state={activeTab?.security?.state} />
 
           {/* URL Input */}
           <input
@@ -243,7 +215,7 @@ export function AddressBar() {
           <div className="absolute top-full left-0 right-0 mt-2 py-2 bg-card/95 backdrop-blur-md rounded-2xl border border-border/50 shadow-lg z-50 animate-in fade-in slide-in-from-top-2 duration-200">
             {suggestions.map((suggestion, index) => (
               <button
-                key={`${suggestion.type}-${suggestion.url}`}
+                key={`${suggestion.kind}-${suggestion.url}`}
                 onClick={() => handleSelectSuggestion(suggestion)}
                 onMouseEnter={() => setSelectedIndex(index)}
                 className={cn(
@@ -252,18 +224,34 @@ export function AddressBar() {
                 )}
               >
                 <div className="w-8 h-8 rounded-lg bg-secondary/50 flex items-center justify-center flex-shrink-0">
-                  {suggestion.icon || (
-                    <Globe className="w-4 h-4 text-muted-foreground" />
-                  )}
+                  {SUGGESTION_ICONS[suggestion.kind]}
                 </div>
                 <div className="flex-1 min-w-0">
                   <p className="text-sm font-medium text-foreground truncate">
-                    {suggestion.title || suggestion.url}
+                    {suggestion.title ? (
+                      <Highlighted
+                        text={suggestion.title}
+                        ranges={suggestion.title_matches}
+                      />
+                    ) : (
+                      <Highlighted
+                        text={suggestion.url}
+                        ranges={suggestion.url_matches}
+                      />
+                    )}
                   </p>
                   <p className="text-xs text-muted-foreground truncate">
-                    {suggestion.type === "search"
-                      ? "Google Search"
-                      : suggestion.url}
+                    {suggestion.kind === "search" ? (
+                      "Search"
+                    ) : (
+                      <>
+                        {suggestion.kind === "tab" && "Switch to tab · "}
+                        <Highlighted
+                          text={suggestion.url}
+                          ranges={suggestion.url_matches}
+                        />
+                      </>
+                    )}
                   </p>
                 </div>
                 {index === selectedIndex && (
@@ -309,3 +297,35 @@ export function AddressBar() {
     </div>
   );
 }
+
+/** Renders `text` with the matched character ranges in bold. */
+function Highlighted({ text, ranges }: { text: string; ranges: MatchRange[] }) {
+  // Ranges count characters, not UTF-16 code units
+  const chars = Array.from(text);
+  const parts: React.ReactNode[] = [];
+  let last = 0;
+  ranges.forEach(({ start, end }) => {
+    parts.push(chars.slice(last, start).join(""));
+    parts.push(
+      <strong key={start} className="font-semibold text-foreground">
+        {chars.slice(start, end).join("")}
+      </strong>
+    );
+    last = end;
+  });
+  parts.push(chars.slice(last).join(""));
+  return <>{parts}</>;
+}
+
+function SecurityIcon({ state }: { state?: SecurityState }) {
+  switch (state) {
+    case "secure":
+      return <Lock className="w-3.5 h-3.5 text-sage-500" />;
+    case "insecure_broken":
+      return <ShieldAlert className="w-3.5 h-3.5 text-destructive" />;
+    case "insecure":
+      return <ShieldAlert className="w-3.5 h-3.5 text-clay-500" />;
+    default:
+      return <Globe className="w-3.5 h-3.5 text-muted-foreground" />;
+  }
+}
@@ -1,61 +1,147 @@
+import { Fragment, useState } from "react";
 import { Plus, X } from "lucide-react";
 import { useBrowserStore } from "@/stores/browserStore";
 import { cn, truncate } from "@/lib/utils";
+import type { GroupColor } from "@/types";
+
+const GROUP_COLORS: Record<GroupColor, string> = {
+  grey: "bg-gray-400",
+  blue: "bg-blue-500",
+  red: "bg-red-500",
+  yellow: "bg-yellow-400",
+  green: "bg-green-500",
+  pink: "bg-pink-400",
+  purple: "bg-purple-500",
+  cyan: "bg-cyan-500",
+  orange: "bg-orange-400",
+};
 
 export function TabBar() {
-  const { tabs, createTab, closeTab, switchTab } = useBrowserStore();
+  const {
+    tabs,
+    groups,
+    createTab,
+    closeTab,
+    switchTab,
+    moveTab,
+    setTabPinned,
+    updateTabGroup,
+    createWindow,
+  } = useBrowserStore();
+  const [draggedTabId, setDraggedTabId] = useState<string | null>(null);
 
   return (
     <div className="flex items-center gap-1 flex-1 overflow-hidden">
       <div className="flex items-center gap-1 overflow-x-auto scrollbar-thin flex-1">
-        {tabs.map((tab) => (
-          <div
-            key={tab.id}
-            onClick={() => switchTab(tab.id)}
-            className={cn(
-              "group flex items-center gap-2 px-3 py-1.5 min-w-[120px] max-w-[200px] rounded-lg cursor-pointer transition-all",
-              "border border-transparent hover:border-border/50",
-              tab.is_active
-                ? "bg-background shadow-sm border-border"
-                : "bg-transparent hover:bg-secondary/50"
-            )}
-          >
-            {/* Favicon */}
-            {tab.favicon ? (
-              <img
-                src={tab.favicon}
-                alt=""
-                className="w-4 h-4 rounded-sm flex-shrink-0"
-                onError={(e) => {
-                  (e.target as HTMLImageElement).style.display = "none";
-                }}
-              />
-            ) : (
-              <div className="w-4 h-4 rounded-full bg-clay-200 flex-shrink-0" />
-            )}
+        {tabs.map((tab, index) => {
+          const group = groups.find((g) => g.id === tab.group_id);
+          const startsGroup =
+            group && tabs[index - 1]?.group_id !== tab.group_id;
+
+          return (
+            <Fragment key={tab.id}>
+              {/* Group header, shown before the first tab of each group */}
+              {group && startsGroup && (
+                <button
+                  onClick={() =>
+                    updateTabGroup(group.id, { collapsed: !group.collapsed })
+                  }
+                  className={cn(
+                    "px-2 py-0.5 min-w-[1.5rem] h-5 rounded-md text-xs font-medium text-white flex-shrink-0",
+                    GROUP_COLORS[group.color]
+                  )}
+                  title={group.collapsed ? "Expand group" : "Collapse group"}
+                >
+                  {group.title}
+                </button>
+              )}
+
+              {!group?.collapsed && (
+                <div
+                  draggable
+                  onDragStart={() => setDraggedTabId(tab.id)}
+                  onDragOver={(e) => e.preventDefault()}
+                  onDrop={(e) => {
+                    e.preventDefault();
+                    if (draggedTabId && draggedTabId !== tab.id) {
+                      moveTab(draggedTabId, index);
+                    }
+                    setDraggedTabId(null);
+                  }}
+                  onDragEnd={(e) => {
+                    // Dropping a tab outside the window tears it off into a new one
+                    const outside =
+                      e.clientX < 0 ||
+                      e.clientY < 0 ||
+                      e.clientX > window.innerWidth ||
+                      e.clientY > window.innerHeight;
+                    if (outside && tabs.length > 1) {
+                      createWindow(tab.id);
+                    }
+                    setDraggedTabId(null);
+                  }}
+                  onClick={() => switchTab(tab.id)}
+                  onContextMenu={(e) => {
+                    e.preventDefault();
+                    setTabPinned(tab.id, !tab.pinned);
+                  }}
+                  title={tab.pinned ? tab.title : undefined}
+                  className={cn(
+                    "group flex items-center gap-2 py-1.5 rounded-lg cursor-pointer transition-all",
+                    tab.pinned
+                      ? "px-2.5 flex-shrink-0"
+                      : "px-3 min-w-[120px] max-w-[200px]",
+                    "border border-transparent hover:border-border/50",
+                    tab.is_active
+                      ? "bg-background shadow-sm border-border"
+                      : "bg-transparent hover:bg-secondary/50",
+                    draggedTabId === tab.id && "opacity-50"
+                  )}
+                >
+                  {/* Favicon */}
+                  {tab.favicon ? (
+                    <img
+                      src={tab.favicon}
+                      alt=""
+                      className="w-4 h-4 rounded-sm flex-shrink-0"
+                      onError={(e) => {
+                        (e.target as HTMLImageElement).style.display = "none";
+                      }}
+                    />
+                  ) : (
+                    <div className="w-4 h-4 rounded-full bg-clay-200 flex-shrink-0" />
+                  )}
 
-            {/* Title */}
-            <span className="text-sm truncate flex-1 text-foreground">
-              {tab.title === "New Tab" && !tab.url
-                ? "New Tab"
-                : truncate(tab.title || "Loading...", 20)}
-            </span>
+                  {/* Pinned tabs show only their favicon */}
+                  {!tab.pinned && (
+                    <>
+                      {/* Title */}
+                      <span className="text-sm truncate flex-1 text-foreground">
+                        {tab.title === "New Tab" && !tab.url
+                          ? "New Tab"
+                          : truncate(tab.title || "Loading...", 20)}
+                      </span>
 
-            {/* Close button */}
-            <button
-              onClick={(e) => {
-                e.stopPropagation();
-                closeTab(tab.id);
-              }}
-              className={cn(
-                "opacity-0 group-hover:opacity-100 p-0.5 rounded-full hover:bg-secondary transition-all",
-                "focus:opacity-100 focus:outline-none"
+                      {/* Close button */}
+                      <button
+                        onClick={(e) => {
+                          e.stopPropagation();
+                          closeTab(tab.id);
+                        }}
+                        className={cn(
+                          "opacity-0 group-hover:opacity-100 p-0.5 rounded-full hover:bg-secondary transition-all",
+                          "focus:opacity-100 focus:outline-none"
+                        )}
+                      >
+                        <X className="w-3.5 h-3.5 text-muted-foreground" />
+                      </button>
+                    </>
+                  )}
+                </div>
               )}
-            >
-              <X className="w-3.5 h-3.5 text-muted-foreground" />
-            </button>
-          </div>
-        ))}
+            </Fragment>
+          );
+        })}
       </div>
 
       {/* New Tab button */}
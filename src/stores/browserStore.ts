@@ -1,16 +1,37 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import { buildFaviconUrl } from "@/lib/utils/favicon";
-import type { Tab, TabId, ViewMode, HistoryEntry } from "@/types";
+import type {
+  Tab,
+  TabId,
+  ViewMode,
+  HistoryEntry,
+  GroupColor,
+  GroupId,
+  TabGroup,
+  TabGroupUpdate,
+  TabStrip,
+  ClosedTab,
+  ReopenedTab,
+  BrowserWindow,
+  WindowId,
+} from "@/types";
+
+// Each Tauri window shows the tab strip of the browser window with its label
+const windowId: WindowId = getCurrentWindow().label;
 
 interface BrowserStore {
   // State
   tabs: Tab[];
+  groups: TabGroup[];
+  windows: BrowserWindow[];
   activeTabId: TabId | null;
   viewMode: ViewMode;
   isAgentPanelOpen: boolean;
   isLoading: boolean;
   history: HistoryEntry[];
+  recentlyClosed: ClosedTab[];
 
   // Tab navigation history (for back/forward)
   tabHistory: Record<TabId, string[]>;
@@ -19,7 +40,26 @@ interface BrowserStore {
   // Actions
   createTab: (url?: string) => Promise<void>;
   closeTab: (tabId: TabId) => Promise<void>;
+  reopenClosedTab: (closedId?: TabId) => Promise<void>;
+  loadRecentlyClosed: () => Promise<void>;
   switchTab: (tabId: TabId) => Promise<void>;
+  switchToRecentTab: () => Promise<void>;
+  moveTab: (tabId: TabId, newIndex: number) => Promise<void>;
+  setTabPinned: (tabId: TabId, pinned: boolean) => Promise<void>;
+  createTabGroup: (
+    tabIds: TabId[],
+    title?: string,
+    color?: GroupColor
+  ) => Promise<void>;
+  updateTabGroup: (groupId: GroupId, update: TabGroupUpdate) => Promise<void>;
+  ungroupTabs: (groupId: GroupId) => Promise<void>;
+  moveTabGroup: (groupId: GroupId, newIndex: number) => Promise<void>;
+  createWindow: (tabId?: TabId) => Promise<void>;
+  moveTabToWindow: (
+    tabId: TabId,
+    targetWindowId: WindowId,
+    index?: number
+  ) => Promise<void>;
   navigateTab: (tabId: TabId, url: string) => Promise<void>;
   goBack: (tabId: TabId) => Promise<void>;
   goForward: (tabId: TabId) => Promise<void>;
@@ -35,18 +75,21 @@ interface BrowserStore {
 
 export const useBrowserStore = create<BrowserStore>((set, get) => ({
   tabs: [],
+  groups: [],
+  windows: [],
   activeTabId: null,
   viewMode: "normal",
   isAgentPanelOpen: false,
   isLoading: false,
   history: [],
+  recentlyClosed: [],
   tabHistory: {},
   tabHistoryIndex: {},
 
   createTab: async (url) => {
     set({ isLoading: true });
     try {
-      const newTab = await invoke<Tab>("create_tab", { url });
+      const newTab = await invoke<Tab>("create_tab", { url, windowId });
       const initialUrl = url || "about:blank";
       set((state) => ({
         tabs: [...state.tabs, newTab],
@@ -70,27 +113,49 @@ export const useBrowserStore = create<BrowserStore>((set, get) => ({
   closeTab: async (tabId) => {
     try {
       await invoke("close_tab", { tabId });
-      const { tabs, activeTabId } = get();
-      const newTabs = tabs.filter((t) => t.id !== tabId);
-
-      // If we closed the active tab, switch to another one
-      let newActiveId = activeTabId;
-      if (activeTabId === tabId) {
-        newActiveId = newTabs.length > 0 ? newTabs[0].id : null;
-        if (newActiveId) {
-          await invoke<Tab>("switch_tab", { tabId: newActiveId });
-        }
-      }
-
-      set({
-        tabs: newTabs,
-        activeTabId: newActiveId,
-      });
+      // The backend picks which tab takes over from a closed active tab
+      await get().refreshTabs();
     } catch (error) {
       console.error("Failed to close tab:", error);
     }
   },
 
+  reopenClosedTab: async (closedId) => {
+    try {
+      const { tab, history, history_index } = await invoke<ReopenedTab>(
+        "reopen_closed_tab",
+        { closedId }
+      );
+      await get().refreshTabs();
+      set((state) => ({
+        recentlyClosed: state.recentlyClosed.filter(
+          (c) => c.id !== (closedId ?? state.recentlyClosed[0]?.id)
+        ),
+        tabHistory: {
+          ...state.tabHistory,
+          [tab.id]: history.map((entry) => entry.url),
+        },
+        tabHistoryIndex: {
+          ...state.tabHistoryIndex,
+          [tab.id]: history_index,
+        },
+      }));
+    } catch (error) {
+      console.error("Failed to reopen closed tab:", error);
+    }
+  },
+
+  loadRecentlyClosed: async () => {
+    try {
+      const recentlyClosed = await invoke<ClosedTab[]>(
+        "get_recently_closed_tabs"
+      );
+      set({ recentlyClosed });
+    } catch (error) {
+      console.error("Failed to load recently closed tabs:", error);
+    }
+  },
+
   switchTab: async (tabId) => {
     try {
       const tab = await invoke<Tab>("switch_tab", { tabId });
@@ -107,6 +172,103 @@ export const useBrowserStore = create<BrowserStore>((set, get) => ({
     }
   },
 
+  moveTab: async (tabId, newIndex) => {
+    try {
+      const tabs = await invoke<Tab[]>("move_tab", { tabId, newIndex });
+      set({ tabs: tabs.filter((t) => t.window_id === windowId) });
+      // Moving a tab can add it to or take it out of a group
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to move tab:", error);
+    }
+  },
+
+  setTabPinned: async (tabId, pinned) => {
+    try {
+      await invoke<Tab>(pinned ? "pin_tab" : "unpin_tab", { tabId });
+      // Pinning moves the tab and can take it out of its group
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to pin tab:", error);
+    }
+  },
+
+  createTabGroup: async (tabIds, title, color) => {
+    try {
+      await invoke<TabGroup>("create_tab_group", { tabIds, title, color });
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to create tab group:", error);
+    }
+  },
+
+  updateTabGroup: async (groupId, update) => {
+    try {
+      const group = await invoke<TabGroup>("update_tab_group", {
+        groupId,
+        update,
+      });
+      set((state) => ({
+        groups: state.groups.map((g) => (g.id === groupId ? group : g)),
+      }));
+    } catch (error) {
+      console.error("Failed to update tab group:", error);
+    }
+  },
+
+  ungroupTabs: async (groupId) => {
+    try {
+      await invoke("ungroup_tabs", { groupId });
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to ungroup tabs:", error);
+    }
+  },
+
+  moveTabGroup: async (groupId, newIndex) => {
+    try {
+      const tabs = await invoke<Tab[]>("move_tab_group", { groupId, newIndex });
+      set({ tabs: tabs.filter((t) => t.window_id === windowId) });
+    } catch (error) {
+      console.error("Failed to move tab group:", error);
+    }
+  },
+
+  createWindow: async (tabId) => {
+    try {
+      await invoke<BrowserWindow>("create_window", { tabId });
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to create window:", error);
+    }
+  },
+
+  moveTabToWindow: async (tabId, targetWindowId, index) => {
+    try {
+      await invoke<Tab>("move_tab_to_window", {
+        tabId,
+        windowId: targetWindowId,
+        index,
+      });
+      await get().refreshTabs();
+    } catch (error) {
+      console.error("Failed to move tab to window:", error);
+    }
+  },
+
+  switchToRecentTab: async () => {
+    try {
+      const recent = (await invoke<Tab[]>("get_recent_tabs")).filter(
+        (t) => t.window_id === windowId
+      );
+      if (recent.length > 1) {
+        await get().switchTab(recent[1].id);
+      }
+    } catch (error) {
+      console.error("Failed to switch to recent tab:", error);
+    }
+  },
+
   navigateTab: async (tabId, url) => {
     try {
       // Fetch favicon for the URL
@@ -240,10 +402,14 @@ export const useBrowserStore = create<BrowserStore>((set, get) => ({
 
   refreshTabs: async () => {
     try {
-      const tabs = await invoke<Tab[]>("get_tabs");
+      const { tabs, groups, windows } = await invoke<TabStrip>("get_tabs", {
+        windowId,
+      });
       const activeTab = tabs.find((t) => t.is_active);
       set({
         tabs,
+        groups,
+        windows,
         activeTabId: activeTab?.id ?? (tabs.length > 0 ? tabs[0].id : null),
       });
     } catch (error) {
@@ -21,6 +21,17 @@ const defaultSettings: Settings = {
   ai_provider: "openai",
   ai_model: "gpt-4o",
   agent_mode_enabled: false,
+  tab_lifecycle_enabled: true,
+  tab_freeze_after_minutes: 5,
+  tab_discard_after_minutes: 30,
+  proxy: {
+    mode: "direct",
+    host: "",
+    port: 0,
+    bypass_list: [],
+  },
+  restore_session: true,
+  tab_close_activation: "recent",
 };
 
 export const useSettingsStore = create<SettingsStore>((set, get) => ({
@@ -6,8 +6,365 @@ export interface Tab {
   url: string;
   title: string;
   favicon?: string;
+  window_id: WindowId;
   position: number;
   is_active: boolean;
+  lifecycle_state: TabLifecycleState;
+  opener_id?: TabId;
+  browser_context_id?: string;
+  group_id?: GroupId;
+  pinned: boolean;
+  security: TabSecurity;
+}
+
+export type TabLifecycleState = "active" | "frozen" | "discarded";
+
+// Tab group types
+export type GroupId = string;
+
+export type GroupColor =
+  | "grey"
+  | "blue"
+  | "red"
+  | "yellow"
+  | "green"
+  | "pink"
+  | "purple"
+  | "cyan"
+  | "orange";
+
+export interface TabGroup {
+  id: GroupId;
+  title: string;
+  color: GroupColor;
+  collapsed: boolean;
+}
+
+export interface TabGroupUpdate {
+  title?: string;
+  color?: GroupColor;
+  collapsed?: boolean;
+}
+
+export interface TabStrip {
+  tabs: Tab[];
+  groups: TabGroup[];
+  windows: BrowserWindow[];
+}
+
+// Window types
+export type WindowId = string;
+
+export interface BrowserWindow {
+  id: WindowId;
+  active_tab?: TabId;
+  tab_count: number;
+}
+
+// Security types
+export type SecurityState =
+  | "unknown"
+  | "neutral"
+  | "insecure"
+  | "secure"
+  | "info"
+  | "insecure_broken";
+
+export interface CertificateDetails {
+  subject_name: string;
+  issuer: string;
+  valid_from: number;
+  valid_to: number;
+  protocol: string;
+  key_exchange: string;
+  cipher: string;
+  network_error?: string;
+}
+
+export interface CertificateError {
+  url: string;
+  host: string;
+  error_type: string;
+}
+
+export interface CertificateDecision {
+  host: string;
+  url: string;
+  error_type: string;
+  decided_at: string;
+}
+
+export interface TabSecurity {
+  state: SecurityState;
+  mixed_content: boolean;
+  certificate?: CertificateDetails;
+  issues: string[];
+  certificate_error?: CertificateError;
+}
+
+// Task manager types
+export interface TabMetrics {
+  tab_id: TabId;
+  js_heap_used_size: number;
+  js_heap_total_size: number;
+  dom_nodes: number;
+  documents: number;
+  frames: number;
+  js_event_listeners: number;
+  layout_count: number;
+  recalc_style_count: number;
+  cpu_time: number;
+  script_duration: number;
+  layout_duration: number;
+}
+
+export interface ProcessInfo {
+  id: number;
+  process_type: string;
+  cpu_time: number;
+  tab_ids: TabId[];
+  memory?: number;
+}
+
+// Find in page types
+export interface FindResult {
+  query: string;
+  match_count: number;
+  current_index?: number;
+}
+
+// Script evaluation types
+export type ScriptWorld = "isolated" | "main";
+
+export interface StackFrame {
+  function_name: string;
+  url: string;
+  line_number: number;
+  column_number: number;
+}
+
+export interface ScriptException {
+  message: string;
+  class_name?: string;
+  line_number: number;
+  column_number: number;
+  url?: string;
+  stack: StackFrame[];
+}
+
+export interface EvaluationResult {
+  value?: unknown;
+  exception?: ScriptException;
+}
+
+// Screencast types
+export interface ScreencastSettings {
+  format?: "jpeg" | "png";
+  quality?: number;
+  max_fps?: number;
+  max_width?: number;
+  max_height?: number;
+}
+
+export interface ScreencastFrame {
+  tab_id: TabId;
+  data: string;
+  format: string;
+  device_width: number;
+  device_height: number;
+  page_scale_factor: number;
+  offset_top: number;
+  scroll_offset_x: number;
+  scroll_offset_y: number;
+  timestamp?: number;
+}
+
+export interface MouseInput {
+  kind: "mousePressed" | "mouseReleased" | "mouseMoved" | "mouseWheel";
+  x: number;
+  y: number;
+  button?: "none" | "left" | "middle" | "right" | "back" | "forward";
+  click_count?: number;
+  delta_x?: number;
+  delta_y?: number;
+  modifiers?: number;
+}
+
+export interface KeyInput {
+  kind: "keyDown" | "keyUp" | "rawKeyDown" | "char";
+  key?: string;
+  code?: string;
+  text?: string;
+  windows_virtual_key_code?: number;
+  modifiers?: number;
+}
+
+// Popup types
+export type PopupPolicy = "allow" | "block" | "redirect";
+
+export interface PopupRule {
+  host: string;
+  policy: PopupPolicy;
+}
+
+// Backend event types (emitted on "browser-event")
+export const EVENT_SCHEMA_VERSION = 1;
+
+export type BrowserEvent =
+  | { type: "tab_created"; tab: Tab }
+  | { type: "tab_updated"; tab: Tab }
+  | { type: "tab_closed"; tab_id: TabId }
+  | { type: "tab_activated"; tab_id: TabId }
+  | { type: "tabs_reordered"; window_id: WindowId; tab_ids: TabId[] }
+  | { type: "tab_group_updated"; group: TabGroup }
+  | { type: "tab_group_removed"; group_id: GroupId }
+  | { type: "window_created"; window_id: WindowId }
+  | { type: "window_closed"; window_id: WindowId }
+  | { type: "navigated"; tab_id: TabId; url: string }
+  | { type: "certificate_error"; tab_id: TabId; error: CertificateError }
+  | { type: "download_started"; guid: string; url: string; suggested_filename: string }
+  | {
+      type: "download_progress";
+      guid: string;
+      received_bytes: number;
+      total_bytes: number;
+      state: "inProgress" | "completed" | "canceled";
+    }
+  | { type: "history_changed" }
+  | { type: "closed_tabs_changed" }
+  | { type: "bookmarks_changed" };
+
+export type BrowserEventEnvelope = BrowserEvent & {
+  version: number;
+  timestamp: string;
+};
+
+// Request rule types
+export type RuleAction =
+  | { type: "modify_headers"; set?: Record<string, string>; remove?: string[] }
+  | { type: "redirect"; url: string }
+  | {
+      type: "fulfill";
+      status?: number;
+      headers?: Record<string, string>;
+      body?: string;
+      file_path?: string;
+    };
+
+export interface RequestRule {
+  id: number;
+  name: string;
+  url_pattern: string;
+  resource_type?: string;
+  action: RuleAction;
+  enabled: boolean;
+}
+
+// Network emulation types
+export type NetworkEmulation =
+  | { preset: "none" }
+  | { preset: "offline" }
+  | { preset: "slow_3g" }
+  | { preset: "fast_3g" }
+  | {
+      preset: "custom";
+      latency_ms: number;
+      download_throughput: number;
+      upload_throughput: number;
+      cpu_slowdown?: number;
+    };
+
+// Zoom types
+export interface ZoomLevel {
+  origin: string;
+  level: number;
+}
+
+// Site data types
+export type SiteDataType =
+  | "cookies"
+  | "local_storage"
+  | "indexed_db"
+  | "cache_storage"
+  | "service_workers";
+
+export interface SiteData {
+  origin: string;
+  cookie_count: number;
+  usage: number;
+  quota: number;
+  usage_breakdown: Record<string, number>;
+  usage_unavailable: boolean;
+}
+
+// Set-of-marks types
+export interface Mark {
+  number: number;
+  backend_node_id: number;
+  tag: string;
+  label?: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface MarkedScreenshot {
+  image: string;
+  marks: Mark[];
+}
+
+// Recently closed tab types
+export interface NavigationEntry {
+  url: string;
+  title: string;
+}
+
+export interface ClosedTab {
+  id: TabId;
+  url: string;
+  title: string;
+  favicon?: string;
+  window_id: WindowId;
+  position: number;
+  group_id?: GroupId;
+  pinned: boolean;
+  history: NavigationEntry[];
+  history_index: number;
+  closed_at: string;
+}
+
+export interface ReopenedTab {
+  tab: Tab;
+  history: NavigationEntry[];
+  history_index: number;
+}
+
+// Omnibox types
+export type SuggestionKind = "tab" | "bookmark" | "history" | "search";
+
+export interface MatchRange {
+  start: number;
+  end: number;
+}
+
+export interface OmniboxSuggestion {
+  kind: SuggestionKind;
+  url: string;
+  title: string;
+  tab_id?: TabId;
+  title_matches: MatchRange[];
+  url_matches: MatchRange[];
+  score: number;
+}
+
+// Named session types
+export interface NamedSession {
+  id: number;
+  name: string;
+  tab_count: number;
+  created_at: string;
+  updated_at: string;
 }
 
 // Agent types
@@ -32,6 +389,7 @@ export interface AgentMessageRequest {
   message: string;
   provider: string;
   model: string;
+  network_emulation?: NetworkEmulation;
 }
 
 export interface AgentMessageResponse {
@@ -64,6 +422,29 @@ export interface Settings {
   ai_provider: string;
   ai_model: string;
   agent_mode_enabled: boolean;
+  tab_lifecycle_enabled: boolean;
+  tab_freeze_after_minutes: number;
+  tab_discard_after_minutes: number;
+  proxy: ProxyConfig;
+  restore_session: boolean;
+  tab_close_activation: TabCloseActivation;
+}
+
+export type TabCloseActivation = "recent" | "opener" | "right";
+
+export type ProxyMode = "direct" | "http" | "https" | "socks5" | "pac";
+
+export interface ProxyConfig {
+  mode: ProxyMode;
+  host: string;
+  port: number;
+  bypass_list: string[];
+  pac_url?: string;
+}
+
+export interface ProxyCredentials {
+  username: string;
+  password: string;
 }
 
 // UI types